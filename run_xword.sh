@@ -1,2 +1,6 @@
 #!/usr/bin/env bash
-cargo rustc --release --bin xword  -- -Z orbit -Z unstable-options && time target/release/xword $@
+# set NIGHTLY=1 to build with the nightly-only optimizations
+toolchain=""
+features=""
+if [ -n "$NIGHTLY" ]; then toolchain="+nightly"; features="--features nightly"; fi
+cargo $toolchain build --release --bin xword $features && time target/release/xword "$@"
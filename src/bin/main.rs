@@ -9,102 +9,804 @@ use getopts::Options;
 use std::env;
 
 use std::str;
-use std::error::Error;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Write};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
+use std::fs;
+use std::process;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-use regex::bytes::Regex;
+use regex::Regex;
 
 use ndarray::{Array, Axis};
 
-use xword::{FixedGrid, Constructor, dim, Orientation, Placement, MatrixDim, LineDim, Problem};
+use xword::{Constructor, RestartStats, Solution, DicEntry, LayoutRules, BlockParams, Symmetry, Preset, WordRuns, WordListFilter, dim, Orientation, Placement, MatrixDim, LineDim, Problem, Numbering, Entry, SvgOptions, SvgView};
 use xword::util;
 
+// exit codes shared by all commands
+const EXIT_OK: i32 = 0;
+const EXIT_INVALID: i32 = 1; // validate found violations, a solution runs off the board, or fill or solve --blocks found no fill
+const EXIT_USAGE: i32 = 2;
+const EXIT_IO: i32 = 3; // an input file could not be read or parsed, or an output file could not be written
+
 fn main() {
-    if let Some(opts) = parse_opts() {
-    	with_opts(opts)
+    let opts = match parse_opts() {
+    	Ok(opts) => opts,
+    	Err(code) => process::exit(code)
+    };
+    
+    match opts.command {
+    	Command::Solve(ref blocks) => with_opts(&opts, blocks.as_ref()),
+    	Command::Fill(ref blocks) => fill(&opts, blocks.as_ref()),
+    	Command::Validate(ref solution_file) => validate(&opts, solution_file),
+    	Command::Render(ref solution_file) => render(&opts, solution_file),
+    	Command::Stats(ref solution_file) => stats(&opts, solution_file.as_ref().map(|file| &**file)),
+    	Command::Bench(runs) => bench(&opts, runs),
+    	Command::Batch { ref inputs, jobs, ref summary } => batch(&opts, inputs, jobs, summary),
+    }
+}
+
+
+fn load_problem(opts: &Opts) -> Problem {
+    match try_load_problem(opts, &opts.prob_file) {
+    	Ok(problem) => problem,
+    	Err((file, msg)) => fail(&file, &msg)
+    }
+}
+
+/// Loads the problem file together with the word lists and clues given in `opts`; errors name the offending file.
+fn try_load_problem(opts: &Opts, prob_file: &str) -> Result<Problem, (String, String)> {
+    let read = |file: &str| try_read_file(file).map_err(|msg| (file.to_string(), msg));
+    
+    let problem = parse_problem(read(prob_file)?, opts.fold_accents).map_err(|msg| (prob_file.to_string(), msg))?;
+    
+    let mut list_words: Vec<String> = vec![];
+    for dic_file in opts.dic_files.iter() {
+    	let words = xword::parse_word_list(&read(dic_file)?);
+    	list_words.extend(opts.dic_filter.apply(words).into_iter().map(|w| w.word));
+    }
+    if let Some(n) = opts.dic_sample {
+    	let rng = util::seeded_rng(opts.seed);
+    	list_words = xword::sample(list_words, n, &*rng);
+    }
+    
+    let file_clues = match opts.clue_file {
+    	Some(ref clue_file) => parse_clue_file(&read(clue_file)?, opts.fold_accents),
+    	None => HashMap::new()
+    };
+    
+    Ok(extend_problem(&problem, list_words, &file_clues, opts.fold_accents))
+}
+
+/// Reads a solution file and swaps its words for the dictionary's own, so that clues and display forms come along.
+fn load_solution(problem: &Problem, solution_file: &str) -> Vec<Placement> {
+    let (_solution_words, mut placements) = match xword::parse_solution(&read_problem(solution_file), &problem.board) {
+    	Ok(parsed) => parsed,
+    	Err(msg) => fail(solution_file, &msg)
+    };
+    
+    // validate reports words on blocked cells, but the grid printers need every letter to be on the board
+    let MatrixDim(h, w) = problem.board.dim();
+    if let Some(place) = placements.iter().find(|place| !place.fold_positions(true, |inside, y, x| inside && y < h && x < w)) {
+    	eprintln!("{}: '{}' at row {}, col {} runs off the {}x{} board", solution_file, place.word, place.y, place.x, h, w);
+    	process::exit(EXIT_INVALID);
     }
+    
+    for place in placements.iter_mut() {
+    	if let Some(word) = problem.dic.iter().find(|word| word.str() == place.word.str()) {
+    		place.word = word.clone();
+    	}
+    }
+    placements
 }
 
 
-fn with_opts(opts: Opts) {
-    let bytes = read_problem(&*opts.prob_file);
+/// Fills the whole grid, American-style, instead of placing as many words as possible. With `blocks`, the solver may block
+/// more cells first.
+fn fill(opts: &Opts, blocks: Option<&BlockParams>) {
+    let mut problem = load_problem(opts);
+    let rng = util::seeded_rng(opts.seed);
+    
+    let filled = match blocks {
+    	Some(params) => xword::fill_with_blocks(&problem.board, &problem.dic, params, &*rng),
+    	None => xword::full_fill(&problem.board, &problem.dic, &*rng)
+    	            .map(|seq| (problem.board.clone(), seq))
+    	            .map_err(|failure| failure.describe()),
+    };
     
-    let problem = parse_problem(bytes);
+    match filled {
+    	Ok((board, seq)) => {
+    		// the blocks the solver added are printed as such
+    		problem.board = board;
+    		publish(opts, &problem, &seq);
+    		if let Some(ref output) = opts.output {
+    			write_file(output, xword::format_solution(&seq).as_bytes());
+    		}
+    	},
+    	Err(msg) => {
+    		println!("{}", msg);
+    		process::exit(EXIT_INVALID);
+    	}
+    }
+}
+
+
+fn validate(opts: &Opts, solution_file: &str) {
+    let problem = load_problem(opts);
+    let placements = load_solution(&problem, solution_file);
     
-    let placements = gen_placements(&problem);
+    let violations = xword::validate(&problem.board, &problem.dic, &placements, &*layout_rules(opts, &problem));
+    for violation in violations.iter() {
+    	println!("{}", violation.describe(&placements));
+    }
     
-    let dic_str : Vec<_> = problem.dic.iter().map(|word| (word.id, String::from_utf8_lossy(&*word.str))).collect();
+    if violations.is_empty() {
+    	println!("OK: {} placements", placements.len());
+    } else {
+    	println!("{} violations", violations.len());
+    	process::exit(EXIT_INVALID);
+    }
+}
+
+
+/// Constructs the crossword; with `blocks`, the layout is then turned into a blocked crossword. Exits with 1 if that fails.
+fn with_opts(opts: &Opts, blocks: Option<&BlockParams>) {
+    let problem = load_problem(opts);
+    
+    let placements = gen_placements(&problem, &*layout_rules(opts, &problem));
+    
+    let dic_str : Vec<_> = problem.dic.iter().map(|word| (word.id, word.to_string())).collect();
     println!("#PLACEMENTS={}, DIC={:?}", placements.len(), dic_str);
     
-	let dim = problem.board.dim();
-	let seq = Constructor::new(dim.0, dim.1, &problem.dic, &placements).construct();
+	let mut constructor = new_constructor(opts, &problem, &placements);
+	if opts.solutions > 1 {
+		let solutions = constructor.construct_top_k(opts.solutions);
+		print_restart_stats(opts, constructor.restart_stats());
+		let mut blocked_all = true;
+		for (i, solution) in solutions.iter().enumerate() {
+			println!("SOLUTION {} of {}: {}", i+1, solutions.len(), solution.summary());
+			
+			// every solution gets its own files: out.sol becomes out-1.sol, out-2.sol, ...
+			let mut numbered = opts.clone();
+			numbered.svg = opts.svg.as_ref().map(|prefix| format!("{}-{}", prefix, i+1));
+			numbered.output = opts.output.as_ref().map(|output| numbered_path(output, i+1));
+			blocked_all &= publish_solution(&numbered, &problem, solution, blocks);
+		}
+		if !blocked_all {
+			process::exit(EXIT_INVALID);
+		}
+		return;
+	}
+	
+	let solution = constructor.construct();
 //	println!("seq = {:?}", seq);
+	print_restart_stats(opts, constructor.restart_stats());
 	
-	for &or in Orientation::values() {
-		println!("------- {:?} -------", or);
-		let moves = seq.iter().cloned().filter(|place| place.orientation == or).collect();
-		print_board(dim.0, dim.1, moves);
+	let blocked = publish_solution(opts, &problem, &solution, blocks);
+	println!("{}", solution.summary());
+	if !blocked {
+		process::exit(EXIT_INVALID);
 	}
 }
 
 
-fn parse_opts() -> Option<Opts> {
+/// Prints the solution and writes its files. With `blocks`, the layout is first turned into a blocked crossword; if that
+/// fails, the layout is published as it is and false is returned.
+fn publish_solution(opts: &Opts, problem: &Problem, solution: &Solution, blocks: Option<&BlockParams>) -> bool {
+	let params = match blocks {
+		Some(params) => params,
+		None => {
+			publish(opts, problem, &solution.placements);
+			if let Some(ref output) = opts.output {
+				write_file(output, solution.export().as_bytes());
+			}
+			return true;
+		}
+	};
+	
+	let rng = util::seeded_rng(opts.seed);
+	match xword::complete_layout(&problem.board, &problem.dic, &solution.placements, params, &*rng) {
+		Ok((board, seq)) => {
+			let added = problem.board.iter().filter(|&&open| open).count() - board.iter().filter(|&&open| open).count();
+			// the blocks the solver added are printed as such
+			let blocked = Problem { dic: problem.dic.clone(), board };
+			publish(opts, &blocked, &seq);
+			println!("blocked grid: {} words, {} cells blocked", seq.len(), added);
+			if let Some(ref output) = opts.output {
+				write_file(output, xword::format_solution(&seq).as_bytes());
+			}
+			true
+		},
+		Err(msg) => {
+			println!("no blocked grid: {}", msg);
+			publish(opts, problem, &solution.placements);
+			if let Some(ref output) = opts.output {
+				write_file(output, solution.export().as_bytes());
+			}
+			false
+		}
+	}
+}
+
+
+/// FILE.EXT with `-n` added to the name: FILE-n.EXT.
+fn numbered_path(path: &str, n: usize) -> String {
+	let file = Path::new(path);
+	match (file.file_stem(), file.extension()) {
+		(Some(stem), Some(ext)) =>
+			file.with_file_name(format!("{}-{}.{}", stem.to_string_lossy(), n, ext.to_string_lossy())).to_string_lossy().into_owned(),
+		_ => format!("{}-{}", path, n),
+	}
+}
+
+
+fn render(opts: &Opts, solution_file: &str) {
+    let problem = load_problem(opts);
+    let placements = load_solution(&problem, solution_file);
+    publish(opts, &problem, &placements);
+}
+
+
+fn stats(opts: &Opts, solution_file: Option<&str>) {
+    let problem = load_problem(opts);
+    
+    let MatrixDim(h, w) = problem.board.dim();
+    let open = problem.board.iter().filter(|&&open| open).count();
+    println!("grid: {}x{}, {} open cells, {} blocked", h, w, open, h * w - open);
+    
+    let clued = problem.dic.iter().filter(|word| word.clue().is_some()).count();
+    println!("dictionary: {} words, {} with clues", problem.dic.len(), clued);
+    
+    let mut lens: BTreeMap<dim, usize> = BTreeMap::new();
+    for word in problem.dic.iter() {
+    	*lens.entry(word.len()).or_insert(0) += 1;
+    }
+    let lens: Vec<String> = lens.iter().map(|(len, count)| format!("{}:{}", len, count)).collect();
+    println!("word lengths: {}", lens.join(" "));
+    
+    let placements = gen_placements(&problem, &*layout_rules(opts, &problem));
+    let mut per_word = vec![0; problem.dic.len()];
+    for place in placements.iter() {
+    	per_word[place.word.id] += 1;
+    }
+    if !per_word.is_empty() {
+    	println!("candidate placements: {} (per word: min {}, avg {:.1}, max {})", placements.len(),
+    	         per_word.iter().min().unwrap(), placements.len() as f32 / per_word.len() as f32, per_word.iter().max().unwrap());
+    }
+    let unplaceable: Vec<String> = problem.dic.iter().filter(|word| per_word[word.id] == 0).map(|word| word.to_string()).collect();
+    if !unplaceable.is_empty() {
+    	println!("words that fit nowhere: {}", unplaceable.join(", "));
+    }
+    
+    if let Some(solution_file) = solution_file {
+    	let placements = load_solution(&problem, solution_file);
+    	let filled = xword::letter_grid(problem.board.dim(), &placements).iter().filter(|cell| cell.is_some()).count();
+    	let violations = xword::validate(&problem.board, &problem.dic, &placements, &*layout_rules(opts, &problem));
+    	println!("solution: {} words placed, {} of {} open cells filled ({:.1}%), {} violations", placements.len(), filled, open,
+    	         100. * filled as f32 / open.max(1) as f32, violations.len());
+    }
+}
+
+
+fn bench(opts: &Opts, runs: usize) {
+    let problem = load_problem(opts);
+    let placements = gen_placements(&problem, &*layout_rules(opts, &problem));
+    
+    let mut times = vec![];
+    let mut placed = vec![];
+    for run in 0..runs {
+    	let solution = construct(opts, &problem, &placements);
+    	let secs = secs(solution.elapsed);
+    	println!("run {}: {} words placed in {:.3}s", run+1, solution.words_placed(), secs);
+    	times.push(secs);
+    	placed.push(solution.words_placed());
+    }
+    
+    if runs > 0 {
+    	let min = times.iter().cloned().fold(f64::INFINITY, f64::min);
+    	let max = times.iter().cloned().fold(0., f64::max);
+    	println!("{} runs: {:.3}s avg, {:.3}s min, {:.3}s max; {:.1} words placed on average", runs,
+    	         times.iter().sum::<f64>() / runs as f64, min, max, placed.iter().sum::<usize>() as f64 / runs as f64);
+    }
+}
+
+
+fn batch(opts: &Opts, inputs: &[String], jobs: usize, summary: &str) {
+    let files = match batch_files(inputs) {
+    	Ok(files) => files,
+    	Err((file, msg)) => fail(&file, &msg)
+    };
+    if files.is_empty() {
+    	fail(&inputs.join(" "), "no problem files found");
+    }
+    
+    // the workers take files off a shared queue and send back (index, row), so the summary keeps the input order
+    let queue = Arc::new(Mutex::new(files.into_iter().enumerate().collect::<Vec<_>>()));
+    let (tx, rx) = mpsc::channel();
+    let workers: Vec<_> = (0..jobs).map(|_| {
+    	let (opts, queue, tx) = (opts.clone(), queue.clone(), tx.clone());
+    	thread::spawn(move || loop {
+    		let next = queue.lock().unwrap().pop();
+    		match next {
+    			Some((i, file)) => tx.send((i, batch_solve(&opts, &file))).unwrap(),
+    			None => break
+    		}
+    	})
+    }).collect();
+    drop(tx);
+    
+    let mut rows: Vec<(usize, BatchRow)> = rx.iter().collect();
+    for worker in workers {
+    	worker.join().unwrap();
+    }
+    rows.sort_by_key(|&(i, _)| i);
+    let rows: Vec<BatchRow> = rows.into_iter().map(|(_, row)| row).collect();
+    
+    for row in rows.iter() {
+    	match row.error {
+    		Some(ref error) => eprintln!("{}: {}", row.file, error),
+    		None => eprintln!("{}: {} words placed, score {}, {:.3}s", row.file, row.placed, row.score, row.runtime),
+    	}
+    }
+    
+    let text = if summary.ends_with(".json") { summary_json(&rows) } else { summary_csv(&rows) };
+    write_file(summary, text.as_bytes());
+    
+    if rows.iter().any(|row| row.error.is_some()) {
+    	process::exit(EXIT_IO);
+    }
+}
+
+/// Solves a single batch file and writes its solution next to it, as FILE.sol.
+fn batch_solve(opts: &Opts, file: &str) -> BatchRow {
+    let mut row = BatchRow { file: file.to_string(), grid: (0, 0), dic_size: 0, placements: 0, score: 0, placed: 0, runtime: 0.,
+                             seed: opts.seed, error: None };
+    
+    let problem = match try_load_problem(opts, file) {
+    	Ok(problem) => problem,
+    	Err((bad_file, msg)) => {
+    		row.error = Some(if bad_file == file { msg } else { format!("{}: {}", bad_file, msg) });
+    		return row;
+    	}
+    };
+    let placements = gen_placements(&problem, &*layout_rules(opts, &problem));
+    let dim = problem.board.dim();
+    
+    let solution = construct(opts, &problem, &placements);
+    row.runtime = secs(solution.elapsed);
+    
+    row.grid = (dim.0, dim.1);
+    row.dic_size = solution.dictionary_size;
+    row.placements = placements.len();
+    row.score = *solution.objective;
+    row.placed = solution.words_placed();
+    
+    let solution_file = Path::new(file).with_extension("sol");
+    if let Err(msg) = try_write_file(&solution_file.to_string_lossy(), solution.export().as_bytes()) {
+    	row.error = Some(format!("{}: {}", solution_file.display(), msg));
+    }
+    row
+}
+
+/// Expands the batch inputs: a directory stands for the `.xword` files in it, and `*` and `?` in a file name match like in
+/// a shell; other inputs are taken as they are.
+fn batch_files(inputs: &[String]) -> Result<Vec<String>, (String, String)> {
+    let mut files = vec![];
+    for input in inputs {
+    	let path = Path::new(input);
+    	let (dir, pattern) = if path.is_dir() {
+    		(path, "*.xword".to_string())
+    	} else if input.contains('*') || input.contains('?') {
+    		let dir = match path.parent() {
+    			Some(dir) if !dir.as_os_str().is_empty() => dir,
+    			_ => Path::new(".")
+    		};
+    		(dir, path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned()))
+    	} else {
+    		files.push(input.clone());
+    		continue;
+    	};
+    	
+    	let entries = fs::read_dir(dir).map_err(|why| (dir.display().to_string(), format!("couldn't list: {}", why)))?;
+    	let pattern: Vec<char> = pattern.chars().collect();
+    	let mut matched: Vec<String> = entries.filter_map(|entry| entry.ok())
+    		.map(|entry| entry.path())
+    		.filter(|path| path.is_file() && path.file_name().is_some_and(|name| {
+    			let name: Vec<char> = name.to_string_lossy().chars().collect();
+    			wildcard_match(&pattern, &name)
+    		}))
+    		.map(|path| path.to_string_lossy().into_owned())
+    		.collect();
+    	matched.sort();
+    	files.extend(matched);
+    }
+    
+    // an overlap of the inputs must not solve a file twice
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    Ok(files)
+}
+
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+    	None => name.is_empty(),
+    	Some((&'*', rest)) => (0..name.len()+1).any(|i| wildcard_match(rest, &name[i..])),
+    	Some((&'?', rest)) => !name.is_empty() && wildcard_match(rest, &name[1..]),
+    	Some((&c, rest)) => name.first() == Some(&c) && wildcard_match(rest, &name[1..]),
+    }
+}
+
+fn summary_csv(rows: &[BatchRow]) -> String {
+    let mut out = "file,grid,dictionary,placements,score,words_placed,runtime_s,seed,error\n".to_string();
+    for row in rows {
+    	let fields = [csv_field(&row.file), format!("{}x{}", row.grid.0, row.grid.1), row.dic_size.to_string(),
+    	              row.placements.to_string(), row.score.to_string(), row.placed.to_string(), format!("{:.3}", row.runtime),
+    	              row.seed.to_string(), csv_field(row.error.as_ref().map_or("", |error| &**error))];
+    	out.push_str(&fields.join(","));
+    	out.push('\n');
+    }
+    out
+}
+
+fn summary_json(rows: &[BatchRow]) -> String {
+    let rows: Vec<String> = rows.iter().map(|row| format!(
+    	"  {{\"file\": {}, \"grid\": [{}, {}], \"dictionary\": {}, \"placements\": {}, \"score\": {}, \"words_placed\": {}, \
+    	 \"runtime_s\": {:.3}, \"seed\": {}, \"error\": {}}}",
+    	json_str(&row.file), row.grid.0, row.grid.1, row.dic_size, row.placements, row.score, row.placed, row.runtime, row.seed,
+    	row.error.as_ref().map_or("null".to_string(), |error| json_str(error))
+    )).collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+    	format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+    	field.to_string()
+    }
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+    	match c {
+    		'"' => out.push_str("\\\""),
+    		'\\' => out.push_str("\\\\"),
+    		'\n' => out.push_str("\\n"),
+    		c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+    		c => out.push(c),
+    	}
+    }
+    out.push('"');
+    out
+}
+
+fn secs(elapsed: Duration) -> f64 {
+    elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 * 1e-9
+}
+
+
+/// With --restarts or --time-budget, shows how the scores of each restart's rollouts were distributed, so that it can be told
+/// whether longer or more restarts pay off.
+fn print_restart_stats(opts: &Opts, stats: &[RestartStats]) {
+	if opts.restarts == 1 && opts.budget.is_none() {
+		return;
+	}
+	
+	println!("restart  seed  rollouts   time  best   mean  median   p90  scores (score:rollouts)");
+	for (i, restart) in stats.iter().enumerate() {
+		let scores: Vec<String> = restart.scores.iter().enumerate()
+		                                 .filter(|&(_, &count)| count > 0)
+		                                 .map(|(score, count)| format!("{}:{}", score, count))
+		                                 .collect();
+		println!("{:>7} {:>5} {:>9} {:>5.1}s {:>5} {:>6.2} {:>7} {:>5}  {}", i+1, restart.seed, restart.rollouts,
+		         secs(restart.elapsed), restart.best, restart.mean(), restart.quantile(0.5), restart.quantile(0.9), scores.join(" "));
+	}
+	println!();
+}
+
+
+/// Prints the grid and the numbered clue lists and writes the SVG files, if requested.
+fn publish(opts: &Opts, problem: &Problem, seq: &[Placement]) {
+	let dim = problem.board.dim();
+	println!("{}", xword::render_text(&problem.board, seq));
+	
+	if problem.has_clues() {
+		for place in seq.iter().filter(|place| place.word.clue().is_none()) {
+			eprintln!("warning: '{}' was placed without a clue", place.word);
+		}
+	}
+	
+	let numbering = Numbering::new(dim, seq);
+	print_entries("ACROSS", &numbering.across);
+	print_entries("DOWN", &numbering.down);
+	
+	if let Some(ref prefix) = opts.svg {
+		for &view in [SvgView::Puzzle, SvgView::Answers].iter() {
+			let svg_opts = SvgOptions { view, cell_size: opts.cell_size, highlight_intersections: opts.highlight };
+			let svg = xword::render_svg(dim, seq, &svg_opts);
+			let suffix = match view {
+				SvgView::Puzzle => "puzzle",
+				SvgView::Answers => "answers",
+			};
+			write_file(&format!("{}-{}.svg", prefix, suffix), svg.as_bytes());
+		}
+	}
+}
+
+
+/// Usage text of every command: its positional arguments and what it does.
+const COMMANDS: &[(&str, &str, &str)] = &[
+	("solve", "[PROBLEM_FILE]", "construct a crossword (the default command)"),
+	("fill", "PROBLEM_FILE", "fill every slot between the blocks with a dictionary word; exits with 1 if there is no such fill"),
+	("validate", "PROBLEM_FILE SOLUTION_FILE", "check a solution file; exits with 1 if it breaks any rule"),
+	("render", "PROBLEM_FILE SOLUTION_FILE", "print a solution file as a grid with clue lists, optionally as SVG"),
+	("export", "PROBLEM_FILE SOLUTION_FILE", "same as render"),
+	("stats", "PROBLEM_FILE [SOLUTION_FILE]", "print dictionary, grid and placement statistics"),
+	("bench", "PROBLEM_FILE", "time repeated constructions"),
+	("batch", "DIR|GLOB...", "solve every .xword file of the given directories or globs, writing FILE.sol next to each and a summary"),
+];
+
+fn parse_opts() -> Result<Opts, i32> {
     let args: Vec<String> = env::args().collect();
     let program = args[0].clone();
-
+    
+    // `xword PROBLEM_FILE` without a command still solves
+    let (command, rest) = match args.get(1).and_then(|arg| COMMANDS.iter().find(|&&(name, _, _)| name == arg)) {
+    	Some(&(name, _, _)) => (name, &args[2..]),
+    	None => ("solve", &args[1..]),
+    };
+    
     let mut opts = Options::new();
     opts.optflag("h", "help", "print this help menu");
-    let matches = match opts.parse(&args[1..]) {
-        Ok(m) => { Some(m) }
-        Err(f) => {
-        	println!("Error: {}\n", f.to_string());
-        	print_usage(&program, &opts);
-        	None 
+    opts.optmulti("", "dict", "load words from FILE, one \"word\" or \"word;score\" per line; can be repeated", "FILE");
+    opts.optopt("", "min-score", "skip word list entries scored below N", "N");
+    opts.optopt("", "min-len", "skip word list entries shorter than N", "N");
+    opts.optopt("", "max-len", "skip word list entries longer than N", "N");
+    opts.optopt("", "sample", "keep a random sample of N word list entries, drawn with --seed if the command takes one", "N");
+    opts.optopt("", "clues", "read additional clues from FILE (word<TAB>clue per line)", "FILE");
+    opts.optflag("", "fold-accents", "drop diacritics from Latin letters when filling the grid (café -> cafe)");
+    // getopts panics when asked about an option it does not know, so every command-specific flag is only looked up for its command
+    let has_output = command == "solve" || command == "fill";
+    let has_solutions = command == "solve";
+    let has_restarts = command == "solve" || command == "bench" || command == "batch";
+    let has_svg = command == "solve" || command == "fill" || command == "render" || command == "export";
+    let has_runs = command == "bench";
+    let has_seed = command == "solve" || command == "fill" || command == "bench" || command == "batch";
+    let has_batch = command == "batch";
+    let has_blocks = command == "fill" || command == "solve";
+    let has_symmetry = has_blocks || command == "bench" || command == "batch";
+    let has_rules = command != "render" && command != "export" && command != "fill";
+    if has_output {
+    	opts.optopt("o", "output", "write the solution to FILE (one \"<row> <col> <H|V> <word>\" line per placement)", "FILE");
+    }
+    if has_solutions {
+    	opts.optopt("", "solutions", "keep the K best sufficiently different solutions; each gets numbered output and SVG files (default: 1)", "K");
+    }
+    if has_restarts {
+    	opts.optopt("", "restarts", "split the search into R independent restarts with their own seeds; keep the best (default: 1). Without --time-budget every restart is a full search, so R restarts take about R times as long", "R");
+    	opts.optopt("", "time-budget", "stop searching after SECS seconds, shared evenly by the restarts; time a restart leaves over goes to the next (default: no limit, every restart searches to the end)", "SECS");
+    }
+    if has_svg {
+    	opts.optopt("", "svg", "write the puzzle and answer key to PREFIX-puzzle.svg and PREFIX-answers.svg", "PREFIX");
+    	opts.optopt("", "cell-size", "SVG cell size in pixels (default: 32)", "PX");
+    	opts.optflag("", "highlight", "shade intersecting cells in the SVG output");
+    }
+    if has_runs {
+    	opts.optopt("", "runs", "number of constructions to time (default: 5)", "N");
+    }
+    if has_seed {
+    	opts.optopt("", "seed", "seed the constructor's random generator (default: 0)", "N");
+    }
+    if has_rules {
+    	opts.optopt("", "rules", "layout rules: strict (American), free-form (the default; corner contacts and parallel words one line apart are fine) or relaxed (free-form plus side by side words whose touching letters are crossed)", "NAME");
+    	opts.optflag("", "word-runs", "let side by side letters stand uncrossed if they make a dictionary word (with relaxed or strict rules)");
+    }
+    if command == "fill" {
+    	opts.optopt("", "blocks", "let the solver block up to N more cells to make the grid fillable", "N");
+    	opts.optopt("", "symmetry", "symmetry of the blocks added with --blocks: rotational (default), mirror or none", "KIND");
+    } else if has_blocks {
+    	opts.optopt("", "blocks", "turn the layout into a blocked crossword, blocking up to N of the cells it leaves empty and filling the rest", "N");
+    	opts.optopt("", "symmetry", "symmetry of the occupied cells, and of the blocks added with --blocks: rotational, mirror or none (default)", "KIND");
+    } else if has_symmetry {
+    	opts.optopt("", "symmetry", "symmetry of the occupied cells: rotational, mirror or none (default)", "KIND");
+    }
+    if has_batch {
+    	opts.optopt("j", "jobs", "solve N files in parallel (default: 1)", "N");
+    	opts.optopt("", "summary", "write the summary to FILE, as JSON if it ends with .json (default: batch-summary.csv)", "FILE");
+    }
+    
+    let usage_error = |msg: &str| {
+    	println!("Error: {}\n", msg);
+    	print_usage(&program, command, &opts);
+    	EXIT_USAGE
+    };
+    
+    let matches = match opts.parse(rest) {
+        Ok(m) => m,
+        Err(f) => return Err(usage_error(&f.to_string()))
+    };
+    
+    if matches.opt_present("h") {
+        print_usage(&program, command, &opts);
+        return Err(EXIT_OK);
+    }
+    
+    let free = &matches.free;
+    let (min_free, max_free) = match command {
+    	"solve" => (0, 1),
+    	"validate" | "render" | "export" => (2, 2),
+    	"stats" => (1, 2),
+    	"batch" => (1, usize::MAX),
+    	_ => (1, 1),
+    };
+    if free.len() < min_free || free.len() > max_free {
+    	return Err(usage_error(&format!("wrong number of arguments for {}", command)));
+    }
+    
+    let prob_file = free.first().cloned().unwrap_or("problem.xword".to_string());
+    
+    let (min_score, min_len, max_len, dic_sample) = match (
+    		parse_num_opt::<i32>(&matches, "min-score"), parse_num_opt::<usize>(&matches, "min-len"),
+    		parse_num_opt::<usize>(&matches, "max-len"), parse_num_opt::<usize>(&matches, "sample")) {
+        (Ok(a), Ok(b), Ok(c), Ok(d)) => (a, b, c, d),
+        _ => return Err(usage_error("--min-score, --min-len, --max-len and --sample expect a number"))
+    };
+    let dic_filter = WordListFilter { min_score, min_len, max_len };
+    
+    let cell_size = match if has_svg { parse_num_opt::<usize>(&matches, "cell-size") } else { Ok(None) } {
+        Ok(cell_size) => cell_size.unwrap_or(32),
+        Err(_) => return Err(usage_error("--cell-size expects a positive integer"))
+    };
+    let runs = match if has_runs { parse_num_opt::<usize>(&matches, "runs") } else { Ok(None) } {
+        Ok(runs) => runs.unwrap_or(5),
+        Err(_) => return Err(usage_error("--runs expects a positive integer"))
+    };
+    let solutions = match if has_solutions { parse_num_opt::<usize>(&matches, "solutions") } else { Ok(None) } {
+        Ok(solutions) if solutions != Some(0) => solutions.unwrap_or(1),
+        _ => return Err(usage_error("--solutions expects a positive integer"))
+    };
+    let restarts = match if has_restarts { parse_num_opt::<usize>(&matches, "restarts") } else { Ok(None) } {
+        Ok(restarts) if restarts != Some(0) => restarts.unwrap_or(1),
+        _ => return Err(usage_error("--restarts expects a positive integer"))
+    };
+    let budget = match if has_restarts { parse_num_opt::<f64>(&matches, "time-budget") } else { Ok(None) } {
+        Ok(None) => None,
+        Ok(Some(secs)) if secs > 0. => Some(Duration::from_millis((secs * 1000.) as u64)),
+        _ => return Err(usage_error("--time-budget expects a positive number of seconds"))
+    };
+    let seed = match if has_seed { parse_num_opt::<u32>(&matches, "seed") } else { Ok(None) } {
+        Ok(seed) => seed.unwrap_or(0),
+        Err(_) => return Err(usage_error("--seed expects a non-negative integer"))
+    };
+    let rules = match if has_rules { matches.opt_str("rules") } else { None } {
+        None => Preset::FreeForm,
+        Some(name) => match Preset::from_name(&name) {
+            Some(rules) => rules,
+            None => return Err(usage_error("--rules expects strict, free-form or relaxed"))
         }
     };
+    let word_runs = has_rules && matches.opt_present("word-runs");
+    if word_runs && rules == Preset::FreeForm {
+    	return Err(usage_error("--word-runs needs --rules relaxed or strict"));
+    }
+    let max_blocks = match if has_blocks { parse_num_opt::<usize>(&matches, "blocks") } else { Ok(None) } {
+        Ok(max_blocks) => max_blocks,
+        Err(_) => return Err(usage_error("--blocks expects a non-negative integer"))
+    };
+    let symmetry = match if has_symmetry { matches.opt_str("symmetry") } else { None } {
+        None if command == "fill" => Symmetry::Rotational,
+        None => Symmetry::None,
+        Some(name) => match &*name {
+            "rotational" => Symmetry::Rotational,
+            "mirror" => Symmetry::Mirror,
+            "none" => Symmetry::None,
+            _ => return Err(usage_error("--symmetry expects rotational, mirror or none"))
+        }
+    };
+    if max_blocks.is_none() && command == "fill" && matches.opt_present("symmetry") {
+    	return Err(usage_error("--symmetry needs --blocks"));
+    }
+    let jobs = match if has_batch { parse_num_opt::<usize>(&matches, "jobs") } else { Ok(None) } {
+        Ok(jobs) if jobs != Some(0) => jobs.unwrap_or(1),
+        _ => return Err(usage_error("--jobs expects a positive integer"))
+    };
     
-    if let Some(matches) = matches {
-    	if matches.opt_present("h") {
-	        print_usage(&program, &opts);
-	        return None;
-	    }
-	    
-	    let prob_file = if !matches.free.is_empty() {
-	        matches.free[0].clone()
-	    } else {
-	        "problem.xword".to_string()
-	    };
-	    
-	    Some(Opts{ prob_file: prob_file })
-    } else {
-    	None
+    let command = match command {
+    	"solve" => Command::Solve(max_blocks.map(|max_blocks| BlockParams { max_blocks, symmetry })),
+    	"fill" => Command::Fill(max_blocks.map(|max_blocks| BlockParams { max_blocks, symmetry })),
+    	"validate" => Command::Validate(free[1].clone()),
+    	"render" | "export" => Command::Render(free[1].clone()),
+    	"stats" => Command::Stats(free.get(1).cloned()),
+    	"bench" => Command::Bench(runs),
+    	_ => Command::Batch { inputs: free.clone(), jobs,
+    	                      summary: matches.opt_str("summary").unwrap_or("batch-summary.csv".to_string()) },
+    };
+    
+    let output = if has_output { matches.opt_str("output") } else { None };
+    let (svg, highlight) = if has_svg { (matches.opt_str("svg"), matches.opt_present("highlight")) } else { (None, false) };
+    
+    Ok(Opts{ command, prob_file, output,
+             dic_files: matches.opt_strs("dict"), dic_filter, dic_sample, clue_file: matches.opt_str("clues"), svg, cell_size, highlight, fold_accents: matches.opt_present("fold-accents"), seed, rules, word_runs, symmetry, solutions, restarts, budget })
+}
+
+fn parse_num_opt<T: str::FromStr>(matches: &getopts::Matches, name: &str) -> Result<Option<T>, ()> {
+    match matches.opt_str(name) {
+        None => Ok(None),
+        Some(s) => s.parse::<T>().map(Some).map_err(|_| ())
     }
 }
 
-fn print_usage(program: &str, opts: &Options) {
-    let brief = format!("Usage: {} [options] [PROBLEM_FILE]", program);
+fn print_usage(program: &str, command: &str, opts: &Options) {
+    let &(_, args, about) = COMMANDS.iter().find(|&&(name, _, _)| name == command).unwrap();
+    let mut brief = format!("Usage: {} {} [options] {}\n\n{}\n\nCommands:", program, command, args, about);
+    for &(name, args, _) in COMMANDS {
+    	brief.push_str(&format!("\n    {} {}", name, args));
+    }
+    brief.push_str(&format!("\n\nExit codes: {} success, {} invalid solution or no fill, {} usage error, {} unreadable or malformed file",
+                            EXIT_OK, EXIT_INVALID, EXIT_USAGE, EXIT_IO));
     print!("{}", opts.usage(&brief));
 }
 
 
-fn print_board(h: dim, w: dim, seq: Vec<Placement>) {
-	let mut rng = util::make_rng();
-	let mut board : FixedGrid<&Placement> = FixedGrid::new(h, w, &mut *rng);
-//			static mut board: Board<Move> = Board::new(self.h, self.w);
-
-//	for mv in seq.iter() {
-//		board.place(mv); 
-//	}
-	board.place_all(seq.iter().collect());
+/// Appends the words loaded from word lists whose grid letters are not in the dictionary yet and attaches the clues from a
+/// clue file.
+fn extend_problem(problem: &Problem, list_words: Vec<String>, file_clues: &HashMap<String, Vec<String>>, fold_accents: bool) -> Problem {
+	let mut entries: Vec<DicEntry> = problem.entries();
 	
-	board.print();
+	let mut known: HashSet<String> = entries.iter().map(|entry| entry.word.clone()).collect();
+	for display in list_words {
+		let entry = DicEntry::from_display(&display, fold_accents);
+		if known.insert(entry.word.clone()) {
+			entries.push(entry);
+		}
+	}
+	
+	for entry in entries.iter_mut() {
+		if let Some(more) = file_clues.get(&entry.word) {
+			entry.clues.extend(more.iter().cloned());
+		}
+	}
+	
+	Problem::from_entries(entries, problem.board.clone())
+}
+
+fn print_entries(title: &str, entries: &[Entry]) {
+	println!("{}", title);
+	for entry in entries {
+		let clue = entry.word.clue().map_or(String::new(), |clue| format!(": {}", clue));
+		println!("{:>4}. {} {} at row {}, col {}{}", entry.number, entry.word.display_form(), entry.word.enumeration(), entry.y, entry.x, clue);
+	}
+	println!();
+}
+
+fn construct(opts: &Opts, problem: &Problem, placements: &[Placement]) -> Solution {
+	new_constructor(opts, problem, placements).construct()
 }
 
-fn gen_placements(problem: &Problem) -> Vec<Placement> {
+fn new_constructor(opts: &Opts, problem: &Problem, placements: &[Placement]) -> Constructor {
+	let dim = problem.board.dim();
+	let mut constructor = Constructor::with_seed(dim.0, dim.1, &problem.dic, placements, opts.seed);
+	constructor.set_rules(layout_rules(opts, problem));
+	constructor.set_symmetry(opts.symmetry);
+	constructor.set_restarts(opts.restarts, opts.budget);
+	constructor
+}
+
+/// The rules picked by --rules; with --word-runs, side by side letters may also make runs that are words of the problem.
+fn layout_rules(opts: &Opts, problem: &Problem) -> Rc<dyn LayoutRules> {
+	let rules = opts.rules.rules();
+	if opts.word_runs { Rc::new(WordRuns::new(rules, &problem.dic)) } else { rules }
+}
+
+/// Every placement of every word in a run of open cells that the layout rules admit, numbered from 0.
+fn gen_placements(problem: &Problem, rules: &dyn LayoutRules) -> Vec<Placement> {
 	let mut sorted = problem.dic.clone();
-	sorted.sort_by(|a, b| a.len().cmp(&b.len()));
+	sorted.sort_by_key(|a| a.len());
 	
 	let mut out_placements = vec![];
 	
@@ -113,7 +815,7 @@ fn gen_placements(problem: &Problem) -> Vec<Placement> {
 	for &orientation in Orientation::values().iter() {
 		let axis = 1 - orientation as usize;
 		for i in 0 .. board.dim()[axis] {
-			let line = board.subview(Axis(axis), i as usize);
+			let line = board.subview(Axis(axis), i);
 			let mut run_len = 0;
 			for j in 0 .. *line.dim() {
 				match line[LineDim(j)] {
@@ -123,13 +825,16 @@ fn gen_placements(problem: &Problem) -> Vec<Placement> {
 							.cloned()
 							.map(|word| 
 								if word.len() <= run_len {
-									placement_id += 1;
 									let (y, x) = orientation.align(i, j + 1 - word.len());
-									Some(Placement::new(placement_id-1, orientation, y, x, word))
+									let place = Placement::new(placement_id, orientation, y, x, word);
+									if rules.admits(&place, board) {
+										placement_id += 1;
+										Some(place)
+									} else { None }
 								} else { None }
 							)
 							.fuse()
-							.flat_map(|word_opt| word_opt)
+							.flatten()
 							.collect();
 						out_placements.extend(placements);
 					},
@@ -146,76 +851,162 @@ fn gen_placements(problem: &Problem) -> Vec<Placement> {
 }
 
 fn read_problem(file: &str) -> Vec<u8> {
+	try_read_file(file).unwrap_or_else(|msg| fail(file, &msg))
+}
+
+fn try_read_file(file: &str) -> Result<Vec<u8>, String> {
 	let path = Path::new(file);
-    let display = path.display();
 
-	// 1. read the file
     // Open the path in read-only mode, returns `io::Result<File>`
-    let mut file = match File::open(&path) {
-        // The `description` method of `io::Error` returns a string that
-        // describes the error
-        Err(why) => panic!("couldn't open {}: {}", display,
-                                                   Error::description(&why)),
+    let mut file = match File::open(path) {
+        Err(why) => return Err(format!("couldn't open: {}", why)),
         Ok(file) => file,
     };
 
     // Read the file contents into a string, returns `io::Result<usize>`
     let mut bytes : Vec<u8> = vec![];
     match file.read_to_end(&mut bytes) {
-        Err(why) => panic!("couldn't read {}: {}", display,
-                                                   Error::description(&why)),
-        Ok(_) => ()
+        Err(why) => Err(format!("couldn't read: {}", why)),
+        Ok(_) => Ok(bytes)
     }
-    
-    bytes
 } 
 
+fn write_file(file: &str, bytes: &[u8]) {
+	try_write_file(file, bytes).unwrap_or_else(|msg| fail(file, &msg))
+}
 
-fn parse_problem(bytes: Vec<u8>) -> Problem {
-    let bytes = bytes.into_iter().map(|b| if b'A'<=b && b<=b'Z' {
-            b'a' + b - b'A'
-        } else {
-            b
-        }
-    ).collect::<Vec<_>>();
-	let re = Regex::new(r"^(?m)(\d{1,3})x(\d{1,3})[\r\n]+((?:[_#]+[\r\n]{1,2})+)-----((?:[\r\n]{1,2}[a-z' ,!-]+)+)[\r\n]*$").unwrap();
-	let caps = re.captures(&bytes).unwrap();
+fn try_write_file(file: &str, bytes: &[u8]) -> Result<(), String> {
+	let path = Path::new(file);
+
+    let mut file = match File::create(path) {
+        Err(why) => return Err(format!("couldn't create: {}", why)),
+        Ok(file) => file,
+    };
+
+    match file.write_all(bytes) {
+        Err(why) => Err(format!("couldn't write: {}", why)),
+        Ok(_) => Ok(())
+    }
+}
+
+/// Reports a file that could not be read, parsed or written and exits.
+fn fail(file: &str, msg: &str) -> ! {
+	eprintln!("{}: {}", file, msg);
+	process::exit(EXIT_IO);
+}
+
+
+fn parse_problem(bytes: Vec<u8>, fold_accents: bool) -> Result<Problem, String> {
+	let text = String::from_utf8(bytes).map_err(|_| "not valid UTF-8".to_string())?;
+	// comment lines may precede the size line, e.g. the xword options probgen suggests for the problem
+	let re = Regex::new(r"^(?m)(?:#[^\r\n]*[\r\n]+)*(\d{1,3})x(\d{1,3})[\r\n]+((?:[_#]+[\r\n]{1,2})+)(?:-----((?:[\r\n]{1,2}[\p{L}' ,!-]+(?:\t[^\r\n]*)?)+))?[\r\n]*$").unwrap();
+	let caps = re.captures(&text).ok_or("expected a HxW line, the grid shape and an optional dictionary after a ----- line".to_string())?;
 	
-	let h = str::from_utf8(caps.at(1).unwrap()).unwrap().parse::<dim>().unwrap();
-	let w = str::from_utf8(caps.at(2).unwrap()).unwrap().parse::<dim>().unwrap();
+	let h = caps.at(1).unwrap().parse::<dim>().unwrap();
+	let w = caps.at(2).unwrap().parse::<dim>().unwrap();
 	let board_str = caps.at(3).unwrap();
 	
-	let dic_str = caps.at(4).unwrap();
+	// every dictionary line is an entry as written, optionally followed by tab-separated clues; a bare shape file has no
+	// dictionary
+	let dic_str = caps.at(4).unwrap_or("");
 	let re = Regex::new(r"(?m)\n?(^.+)").unwrap();
-	let mut dic : Vec<Vec<u8>> = vec![];
+	let mut dic : Vec<DicEntry> = vec![];
     for cap in re.captures_iter(dic_str) {
-    	let cap = cap.at(1).unwrap();
-        let word = cap.to_vec();
-        dic.push(word);
+    	let mut fields = cap.at(1).unwrap().split('\t');
+        let mut entry = DicEntry::from_display(fields.next().unwrap().trim(), fold_accents);
+        if entry.word.is_empty() {
+        	continue;
+        }
+        entry.clues = fields.filter_map(parse_clue).collect();
+        dic.push(entry);
     }
     
 	let mut board: Array<bool, MatrixDim> = Array::default(MatrixDim(h, w));
 	let re = Regex::new(r"(?m)\n?(^[_#]+)").unwrap();
-    for (j, cap) in re.captures_iter(board_str).enumerate() {
-    	for (i, &c) in cap.at(1).unwrap().iter().enumerate() {
+	let rows: Vec<&str> = re.captures_iter(board_str).map(|cap| cap.at(1).unwrap()).collect();
+	if rows.len() != h || rows.iter().any(|row| row.len() != w) {
+		return Err(format!("the grid shape does not match its size {}x{}", h, w));
+	}
+    for (j, row) in rows.into_iter().enumerate() {
+    	for (i, c) in row.chars().enumerate() {
     		let (i, j) = (i as dim, j as dim);
     		match c {
-    			b'_' => board[MatrixDim(j, i)] = true,
-    			b'#' => board[MatrixDim(j, i)] = false,
-    			_    => panic!("unexpected char: {}", c)
+    			'_' => board[MatrixDim(j, i)] = true,
+    			'#' => board[MatrixDim(j, i)] = false,
+    			_   => panic!("unexpected char: {}", c)
     		}
     	}
     }
     
-//	let (dic, dic_arena) = dic_arena::dic_arena(dic);
+    Ok(Problem::from_entries(dic, board))
+}
+
+/// Reads a clue file with `word<TAB>clue` lines into a map from the word's grid letters to its clues.
+fn parse_clue_file(bytes: &[u8], fold_accents: bool) -> HashMap<String, Vec<String>> {
+	let mut clues: HashMap<String, Vec<String>> = HashMap::new();
+	for line in String::from_utf8_lossy(bytes).lines() {
+		let mut fields = line.split('\t');
+		let word = xword::normalize(fields.next().unwrap(), fold_accents);
+		let word_clues: Vec<String> = fields.filter_map(parse_clue).collect();
+		if !word.is_empty() && !word_clues.is_empty() {
+			clues.entry(word).or_default().extend(word_clues);
+		}
+	}
 	
-    Problem::new(dic, board)
+	clues
+}
+
+fn parse_clue(field: &str) -> Option<String> {
+	let clue = field.trim();
+	if clue.is_empty() { None } else { Some(clue.to_string()) }
 }
 
 
 
+#[derive(Clone)]
+enum Command {
+	Solve(Option<BlockParams>),
+	Fill(Option<BlockParams>),
+	Validate(String),
+	Render(String),
+	Stats(Option<String>),
+	Bench(usize),
+	Batch { inputs: Vec<String>, jobs: usize, summary: String },
+}
+
+#[derive(Clone)]
 struct Opts {
-	prob_file: String
+	command: Command,
+	prob_file: String,
+	output: Option<String>,
+	dic_files: Vec<String>,
+	dic_filter: WordListFilter,
+	dic_sample: Option<usize>,
+	clue_file: Option<String>,
+	svg: Option<String>,
+	cell_size: usize,
+	highlight: bool,
+	fold_accents: bool,
+	seed: u32,
+	rules: Preset,
+	word_runs: bool,
+	symmetry: Symmetry,
+	solutions: usize,
+	restarts: usize,
+	budget: Option<Duration>
+}
+
+/// One line of the batch summary.
+struct BatchRow {
+	file: String,
+	grid: (dim, dim),
+	dic_size: usize,
+	placements: usize,
+	score: i32,
+	placed: usize,
+	runtime: f64,
+	seed: u32,
+	error: Option<String>,
 }
 
 
@@ -225,19 +1016,136 @@ mod placement_tests {
 	use xword::*;
 	use ndarray::*;
 
-	fn word(str: &'static [u8]) -> Vec<u8> {
-        str.to_vec()
+	fn word(str: &str) -> String {
+        str.to_string()
 	}
 	
 	#[test]
 	fn placement_ids_are_nat() {
 		let grid: Array<bool, MatrixDim> = Array::default(MatrixDim(4, 3));
-		let words = vec![word(b"ab"), word(b"bc"), word(b"cde"), word(b"cdef"), word(b"fedc"), word(b"fedcb")];
+		let words = vec![word("ab"), word("bc"), word("cde"), word("cdef"), word("fedc"), word("fedcb")];
 		let problem = Problem::new(words, grid);
-		let places = super::gen_placements(&problem);
+		let places = super::gen_placements(&problem, &FreeForm);
 		
 		for (i, place) in places.into_iter().enumerate() {
-			assert_eq!(i, place.id.0 as usize)
+			assert_eq!(i, place.id.0)
 		}
 	}
 }	
+
+
+#[cfg(test)]
+mod parse_tests {
+	#[test]
+	fn dictionary_lines_carry_clues() {
+		let bytes = b"2x2\n__\n__\n-----\nAB\tFirst letters\tStart\ncd\n".to_vec();
+		let problem = super::parse_problem(bytes, false).unwrap();
+		
+		assert_eq!(problem.dic[0].to_string(), "ab");
+		assert_eq!(problem.dic[0].clues(), &["First letters".to_string(), "Start".to_string()]);
+		assert_eq!(problem.dic[1].to_string(), "cd");
+		assert_eq!(problem.dic[1].clue(), None);
+		
+		let file_clues = super::parse_clue_file(b"cd\tAfter ab\nef\tLast\n", false);
+		let problem = super::extend_problem(&problem, vec!["ef".to_string(), "ab".to_string()], &file_clues, false);
+		assert_eq!(problem.dic.len(), 3);
+		assert_eq!(problem.dic[0].clue(), Some("First letters"));
+		assert_eq!(problem.dic[1].clue(), Some("After ab"));
+		assert_eq!((problem.dic[2].to_string(), problem.dic[2].clue()), ("ef".to_string(), Some("Last")));
+	}
+	
+	#[test]
+	fn comments_before_the_size_line() {
+		let bytes = b"# xword options: --rules relaxed --word-runs\n2x2\n#_\n__\n-----\nab\n".to_vec();
+		let problem = super::parse_problem(bytes, false).unwrap();
+		
+		assert!(!problem.board[super::MatrixDim(0, 0)] && problem.board[super::MatrixDim(0, 1)]);
+		assert_eq!(problem.dic[0].to_string(), "ab");
+	}
+	
+	#[test]
+	fn non_latin_dictionary() {
+		let bytes = "2x3\n___\n___\n-----\nШАР\tБалон\nשלום\nÉté\n".as_bytes().to_vec();
+		let problem = super::parse_problem(bytes, false).unwrap();
+		
+		let words: Vec<_> = problem.dic.iter().map(|word| (word.to_string(), word.len())).collect();
+		assert_eq!(words, vec![("шар".to_string(), 3), ("שלום".to_string(), 4), ("été".to_string(), 3)]);
+		assert_eq!(problem.dic[0][1], 'а');
+		assert_eq!(problem.dic[0].clue(), Some("Балон"));
+	}
+	
+	#[test]
+	fn multi_word_entries() {
+		let bytes = "1x9\n_________\n-----\nRock 'n' Roll\tGenre\nCafé au lait\n".as_bytes().to_vec();
+		let problem = super::parse_problem(bytes, true).unwrap();
+		
+		assert_eq!(problem.dic[0].to_string(), "rocknroll");
+		assert_eq!((problem.dic[0].display_form(), problem.dic[0].enumeration()), ("Rock 'n' Roll".to_string(), "(4,1,4)".to_string()));
+		assert_eq!(problem.dic[1].to_string(), "cafeaulait");
+		
+		let file_clues = super::parse_clue_file("rock n roll\tElvis's music\n".as_bytes(), true);
+		let problem = super::extend_problem(&problem, vec!["ROCK-N-ROLL".to_string()], &file_clues, true);
+		assert_eq!(problem.dic.len(), 2);
+		assert_eq!(problem.dic[0].clues().len(), 2);
+	}
+	
+	#[test]
+	fn shape_file_without_dictionary() {
+		let problem = super::parse_problem(b"2x3\n__#\n___\n".to_vec(), false).unwrap();
+		assert_eq!(problem.board.dim(), ::xword::MatrixDim(2, 3));
+		assert!(problem.dic.is_empty());
+		
+		assert!(super::parse_problem(b"2x3\n__#\n__\n".to_vec(), false).is_err());
+		assert!(super::parse_problem(b"2x3\n".to_vec(), false).is_err());
+	}
+}
+
+
+#[cfg(test)]
+mod batch_tests {
+	use super::BatchRow;
+	
+	fn matches(pattern: &str, name: &str) -> bool {
+		let (pattern, name): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+		super::wildcard_match(&pattern, &name)
+	}
+	
+	#[test]
+	fn wildcards() {
+		assert!(matches("*.xword", "week1.xword"));
+		assert!(matches("week?.xword", "week1.xword"));
+		assert!(matches("*", ""));
+		assert!(!matches("*.xword", "week1.sol"));
+		assert!(!matches("week?.xword", "week10.xword"));
+	}
+	
+	#[test]
+	fn summary_formats() {
+		let rows = vec![
+			BatchRow { file: "a,b.xword".to_string(), grid: (15, 15), dic_size: 40, placements: 900, score: 12, placed: 14,
+			           runtime: 1.5, seed: 7, error: None },
+			BatchRow { file: "c.xword".to_string(), grid: (0, 0), dic_size: 0, placements: 0, score: 0, placed: 0,
+			           runtime: 0., seed: 7, error: Some("bad \"grid\"".to_string()) },
+		];
+		
+		let csv = super::summary_csv(&rows);
+		assert_eq!(csv.lines().nth(1), Some("\"a,b.xword\",15x15,40,900,12,14,1.500,7,"));
+		assert_eq!(csv.lines().nth(2), Some("c.xword,0x0,0,0,0,0,0.000,7,\"bad \"\"grid\"\"\""));
+		
+		let json = super::summary_json(&rows);
+		assert!(json.contains("\"file\": \"a,b.xword\", \"grid\": [15, 15]"));
+		assert!(json.contains("\"error\": null"));
+		assert!(json.contains("\"error\": \"bad \\\"grid\\\"\""));
+	}
+}
+
+
+#[cfg(test)]
+mod output_tests {
+	#[test]
+	fn numbered_paths() {
+		assert_eq!(super::numbered_path("out.sol", 2), "out-2.sol");
+		assert_eq!(super::numbered_path("dir.d/out", 1), "dir.d/out-1");
+		assert_eq!(super::numbered_path("week1.xword.sol", 3), "week1.xword-3.sol");
+	}
+}
@@ -2,76 +2,363 @@ extern crate xword;
 extern crate regex;
 extern crate ndarray;
 extern crate rand;
+extern crate getopts;
 
+use getopts::Options;
+use std::env;
 use std::str;
-use std::error::Error;
 use std::fs::{File, OpenOptions};
 use std::io::prelude::*;
 use std::path::Path;
+use std::collections::HashSet;
+use std::rc::Rc;
 
-use regex::bytes::Regex;
-use ndarray::{OwnedArray, Axis, ArrayView};
-use rand::distributions::Range;
+use regex::Regex;
+use ndarray::{Array, Axis, ArrayView};
 
-use xword::{dim, MatrixDim, LineDim, Problem, Orientation};
-use xword::util::tl_rng;
+use xword::{dim, MatrixDim, LineDim, Problem, Placement, Orientation, LetterDist, Symmetry, TemplateParams, LayoutRules, Preset, WordRuns};
+use xword::util::{tl_rng, seeded_rng, AbstractRng};
+use rand::distributions::Range;
 
 fn main() {
-    let bytes = read_template();
+    let opts = match parse_opts() {
+    	Some(opts) => opts,
+    	None => return
+    };
     
-    let mut templ: OwnedArray<u8, MatrixDim> = parse(bytes);
+    // without a seed every run generates a different problem
+    let rng = match opts.seed {
+    	Some(seed) => seeded_rng(seed),
+    	None => tl_rng()
+    };
+    
+    let mut templ: Array<char, MatrixDim> = match opts.generate {
+    	Some(ref params) => match xword::random_template(params, &*rng) {
+    		Ok(templ) => templ,
+    		Err(msg) => panic!("couldn't generate a template: {}", msg)
+    	},
+    	None => parse(read_file(&opts.template))
+    };
     
-    fill(&mut templ);
+    let mut distractors = vec![];
+    if let Some(ref words) = opts.words {
+    	if let Err(msg) = xword::fill_with_words(&mut templ, words, &*rng) {
+    		panic!("couldn't fill the template with words: {}", msg);
+    	}
+    	distractors = pick_distractors(&templ, words, opts.distractors, &*rng);
+    }
+    // cells outside of any slot get random letters, with or without a word list; a layout uses every word once, so random
+    // letters are redrawn until the runs of the template spell distinct words
+    let open = templ.clone();
+    for attempt in 1.. {
+    	fill(&mut templ, &opts.letters, &*rng);
+    	match repeated_run(&templ) {
+    		None => break,
+    		Some(word) if attempt == FILL_ATTEMPTS => panic!("the template repeats the word \"{}\" after {} fills; a layout uses every word once", word, attempt),
+    		Some(_) => templ = open.clone(),
+    	}
+    }
     
     print(&templ);
     
-    let problem = gen_problem(&mut templ);
+    let mut extra = distractors;
+    extra.extend(make_decoys(&templ, opts.decoys, &*rng));
+    let (problem, reference) = gen_problem(&mut templ, extra, opts.shuffle, &*rng);
+    
+    // the filled template is a layout of the whole dictionary; its objective is what the constructor should reach under rules
+    // that allow the layout, so both files say which xword options those are
+    let (rules, word_runs) = opts.rules.unwrap_or_else(|| implied_rules(&problem, &reference));
+    let options = format!("# xword options: --rules {}{}\n", rules.name(), if word_runs { " --word-runs" } else { "" });
+    write_problem(&opts.output, &problem, &options);
+    
+    let violations = xword::validate(&problem.board, &problem.dic, &reference, &*layout_rules(rules, word_runs, &problem));
+    let mut content = format!("# reference solution of {}\n{}", opts.output, options);
+    if violations.is_empty() {
+    	let objective = *xword::objective(problem.board.dim(), &reference);
+    	println!("reference solution: {} words, objective {} ({} rules{})", reference.len(), objective, rules.name(),
+    	         if word_runs { " with word runs" } else { "" });
+    	content.push_str(&format!("# objective: {}\n", objective));
+    } else {
+    	println!("warning: the reference solution breaks the {} layout rules, so it has no objective to compare against:", rules.name());
+    	for violation in violations.iter() {
+    		println!("  {}", violation.describe(&reference));
+    	}
+    }
+    content.push_str(&xword::format_solution(&reference));
+    write_file(&opts.solution, content.as_bytes());
+}
+
+const FILL_ATTEMPTS: usize = 100;
+
+/// A word that two runs of the filled template spell, if any.
+fn repeated_run(templ: &Array<char, MatrixDim>) -> Option<String> {
+	let mut seen = HashSet::new();
+	xword::slots(templ).iter()
+		.map(|slot| slot.cells().into_iter().map(|cell| templ[cell]).collect::<String>())
+		.find(|word| !seen.insert(word.clone()))
+}
+
+/// The rules the filled template implies: free-form if they allow the reference layout, otherwise relaxed rules that let
+/// every run stand as a word, which a dense template needs for its side by side words.
+fn implied_rules(problem: &Problem, reference: &[Placement]) -> (Preset, bool) {
+	if xword::validate(&problem.board, &problem.dic, reference, &*Preset::FreeForm.rules()).is_empty() {
+		(Preset::FreeForm, false)
+	} else {
+		(Preset::Relaxed, true)
+	}
+}
+
+/// The layout rules xword uses for `--rules <preset>`, plus `--word-runs` if `word_runs` is set.
+fn layout_rules(rules: Preset, word_runs: bool, problem: &Problem) -> Rc<dyn LayoutRules> {
+	if word_runs { Rc::new(WordRuns::new(rules.rules(), &problem.dic)) } else { rules.rules() }
+}
+
+fn parse_opts() -> Option<Opts> {
+    let args: Vec<String> = env::args().collect();
+    let program = args[0].clone();
+
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optopt("t", "template", "read the template from FILE (default: template.xtempl)", "FILE");
+    opts.optopt("o", "output", "write the problem to FILE (default: out_problem.xword)", "FILE");
+    opts.optopt("s", "solution", "write the reference solution to FILE (default: the output file with a .sol extension)", "FILE");
+    opts.optopt("", "seed", "seed the random generator to get the same problem on every run", "N");
+    opts.optopt("", "size", "generate a random HxW template instead of reading one", "HxW");
+    opts.optopt("", "density", "fraction of blocked cells in a generated template (default: 0.15)", "F");
+    opts.optopt("", "stars", "fraction of open cells left without a letter in a generated template (default: 0)", "F");
+    opts.optopt("", "symmetry", "block symmetry of a generated template: rotational (default), mirror or none", "KIND");
+    opts.optopt("", "words", "fill the template with words from FILE (one \"word\" or \"word;score\" per line) instead of random letters", "FILE");
+    opts.optopt("", "distractors", "add N words from the --words list that are not part of the hidden solution (default: 0)", "N");
+    opts.optopt("", "decoys", "add N scrambled copies of the hidden words to the dictionary (default: 0)", "N");
+    opts.optflag("", "shuffle", "list the dictionary in random order instead of the order the words appear in the grid");
+    opts.optopt("", "letters", "letter distribution: uniform (default), english, or a FILE of \"<letter> <weight>\" lines", "DIST");
+    opts.optopt("", "rules", "score the reference solution with these xword layout rules: strict, free-form or relaxed (default: free-form if the layout allows it, else relaxed with --word-runs)", "NAME");
+    opts.optflag("", "word-runs", "let side by side letters of the reference stand uncrossed if they make a dictionary word (with --rules relaxed or strict)");
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => {
+        	println!("Error: {}\n", f);
+        	print_usage(&program, &opts);
+        	return None;
+        }
+    };
+    
+    if matches.opt_present("h") || !matches.free.is_empty() {
+        print_usage(&program, &opts);
+        return None;
+    }
+    
+    let seed = match matches.opt_str("seed").map(|s| s.parse::<u32>()) {
+        None => None,
+        Some(Ok(seed)) => Some(seed),
+        Some(Err(_)) => {
+            println!("Error: --seed expects a non-negative integer\n");
+            print_usage(&program, &opts);
+            return None;
+        }
+    };
+    
+    let generate = match matches.opt_str("size") {
+        None => None,
+        Some(size) => match parse_template_params(&size, &matches) {
+            Ok(params) => Some(params),
+            Err(msg) => {
+                println!("Error: {}\n", msg);
+                print_usage(&program, &opts);
+                return None;
+            }
+        }
+    };
+    
+    let letters = match matches.opt_str("letters") {
+        None => LetterDist::uniform(),
+        Some(ref dist) if dist == "uniform" => LetterDist::uniform(),
+        Some(ref dist) if dist == "english" => LetterDist::english(),
+        Some(file) => match LetterDist::parse(&read_file(&file)) {
+            Ok(dist) => dist,
+            Err(msg) => panic!("couldn't parse {}: {}", file, msg)
+        }
+    };
+    
+    let output = matches.opt_str("output").unwrap_or("out_problem.xword".to_string());
+    let solution = matches.opt_str("solution").unwrap_or(Path::new(&output).with_extension("sol").to_string_lossy().into_owned());
+    
+    let distractors = match matches.opt_str("distractors").map(|n| n.parse::<usize>()) {
+        None => 0,
+        Some(Ok(n)) if matches.opt_present("words") => n,
+        Some(Ok(_)) => {
+            println!("Error: --distractors needs --words\n");
+            print_usage(&program, &opts);
+            return None;
+        }
+        Some(Err(_)) => {
+            println!("Error: --distractors expects a non-negative integer\n");
+            print_usage(&program, &opts);
+            return None;
+        }
+    };
+    
+    let decoys = match matches.opt_str("decoys").map(|n| n.parse::<usize>()) {
+        None => 0,
+        Some(Ok(n)) => n,
+        Some(Err(_)) => {
+            println!("Error: --decoys expects a non-negative integer\n");
+            print_usage(&program, &opts);
+            return None;
+        }
+    };
+    
+    let rules = match matches.opt_str("rules").map(|name| Preset::from_name(&name)) {
+        None if matches.opt_present("word-runs") => {
+            println!("Error: --word-runs needs --rules relaxed or strict\n");
+            print_usage(&program, &opts);
+            return None;
+        }
+        None => None,
+        Some(Some(Preset::FreeForm)) if matches.opt_present("word-runs") => {
+            println!("Error: --word-runs needs --rules relaxed or strict\n");
+            print_usage(&program, &opts);
+            return None;
+        }
+        Some(Some(rules)) => Some((rules, matches.opt_present("word-runs"))),
+        Some(None) => {
+            println!("Error: --rules expects strict, free-form or relaxed\n");
+            print_usage(&program, &opts);
+            return None;
+        }
+    };
+    
+    let words = matches.opt_str("words").map(|file| {
+        let mut words: Vec<String> = xword::parse_word_list(&read_file(&file)).iter()
+            .map(|word| xword::normalize(&word.word, false))
+            .filter(|word| word.chars().count() > 1)
+            .collect();
+        let mut seen = HashSet::new();
+        words.retain(|word| seen.insert(word.clone()));
+        words
+    });
+    
+    Some(Opts { template: matches.opt_str("template").unwrap_or("template.xtempl".to_string()),
+                output, solution,
+                seed, generate, words, distractors, decoys,
+                shuffle: matches.opt_present("shuffle"), letters, rules })
+}
+
+fn parse_template_params(size: &str, matches: &getopts::Matches) -> Result<TemplateParams, String> {
+    let dims: Vec<dim> = size.split('x').filter_map(|d| d.parse::<dim>().ok()).filter(|&d| d > 0).collect();
+    if dims.len() != 2 || size.split('x').count() != 2 {
+        return Err("--size expects HxW, e.g. 15x15".to_string());
+    }
+    let (h, w) = (dims[0], dims[1]);
+    
+    let fraction = |name: &str, default: f32| match matches.opt_str(name).map(|f| f.parse::<f32>()) {
+        None => Ok(default),
+        Some(Ok(f)) if (0. ..=1.).contains(&f) => Ok(f),
+        _ => Err(format!("--{} expects a number between 0 and 1", name))
+    };
+    
+    let symmetry = match matches.opt_str("symmetry").as_deref() {
+        None | Some("rotational") => Symmetry::Rotational,
+        Some("mirror") => Symmetry::Mirror,
+        Some("none") => Symmetry::None,
+        Some(other) => return Err(format!("unknown symmetry \"{}\"", other))
+    };
     
-    write_problem(&problem);
+    Ok(TemplateParams { h, w, block_density: fraction("density", 0.15)?, star_fraction: fraction("stars", 0.)?,
+                        symmetry })
+}
+
+fn print_usage(program: &str, opts: &Options) {
+    let brief = format!("Usage: {} [options]", program);
+    print!("{}", opts.usage(&brief));
 }
 
-fn print(filled: &OwnedArray<u8, MatrixDim>) {
+fn print(filled: &Array<char, MatrixDim>) {
 	for (MatrixDim(_, x), c) in filled.indexed_iter() {
 		if x==0 {
 			print!("\n");
 		}
-		print!("{}", *c as char);
+		print!("{}", c);
 	}
 	println!("");
 }
 
-fn fill(templ: &mut OwnedArray<u8, MatrixDim>) {
-	let rng = tl_rng();
-	let range = Range::new(b'a', b'z');
-	
+fn fill(templ: &mut Array<char, MatrixDim>, letters: &LetterDist, rng: &dyn AbstractRng) {
 	for j in 0..templ.dim()[0] {
 		for i in 0..templ.dim()[1] {
 			let idx = MatrixDim(j, i);
 			match templ[idx] {
-				b'_' => templ[idx] = rng.gen_u8(range),
-				b'#' | b'*' => (),
-				_	=> panic!("should never happen")
+				'_' => templ[idx] = letters.sample(rng),
+				_ => (), // blocks, stars and letters placed by the word fill
 			}
 		}
 	}
 }
 
-fn gen_problem(templ: &mut OwnedArray<u8, MatrixDim>) -> Problem {
+/// Picks `n` words of the list that are not in the filled template and short enough to fit into one of its slots.
+fn pick_distractors(templ: &Array<char, MatrixDim>, words: &[String], n: usize, rng: &dyn AbstractRng) -> Vec<String> {
+	let slots = xword::slots(templ);
+	let max_len = slots.iter().map(|slot| slot.len).max().unwrap_or(0);
+	let hidden: HashSet<String> = slots.iter().map(|slot| slot.cells().into_iter().map(|cell| templ[cell]).collect()).collect();
+	
+	let pool: Vec<String> = words.iter()
+		.filter(|word| !hidden.contains(*word) && word.chars().count() <= max_len)
+		.cloned()
+		.collect();
+	if pool.len() < n {
+		println!("warning: only {} distractors available", pool.len());
+	}
+	xword::sample(pool, n, rng)
+}
+
+/// Makes up to `n` words by scrambling the letters of the template's words, skipping those that match any of its words.
+fn make_decoys(templ: &Array<char, MatrixDim>, n: usize, rng: &dyn AbstractRng) -> Vec<String> {
+	let hidden: Vec<Vec<char>> = xword::slots(templ).iter()
+		.map(|slot| slot.cells().into_iter().map(|cell| templ[cell]).collect())
+		.collect();
+	
+	let mut decoys: Vec<Vec<char>> = vec![];
+	for _ in 0..n*100 {
+		if decoys.len() == n || hidden.is_empty() {
+			break;
+		}
+		let mut decoy = hidden[rng.gen_usize(Range::new(0, hidden.len()))].clone();
+		for i in 0..decoy.len() {
+			let j = rng.gen_usize(Range::new(i, decoy.len()));
+			decoy.swap(i, j);
+		}
+		if !hidden.contains(&decoy) && !decoys.contains(&decoy) {
+			decoys.push(decoy);
+		}
+	}
+	if decoys.len() < n {
+		println!("warning: only {} decoys could be made", decoys.len());
+	}
+	decoys.into_iter().map(|decoy| decoy.into_iter().collect()).collect()
+}
+
+/// Extracts every run of two or more letters as a dictionary word and appends the `extra` words, optionally shuffling the
+/// result. The second result places each run's word, shared with the returned problem, where it was found.
+fn gen_problem(templ: &mut Array<char, MatrixDim>, extra: Vec<String>, shuffle: bool, rng: &dyn AbstractRng)
+		-> (Problem, Vec<Placement>) {
 	let h = templ.dim()[0];
 	let w = templ.dim()[1];
 	
-	let mut board: OwnedArray<bool, MatrixDim> = OwnedArray::default(MatrixDim(h, w));
+	let mut board: Array<bool, MatrixDim> = Array::default(MatrixDim(h, w));
 	
-	let mut dic : Vec<Vec<u8>> = vec![];
+	let mut dic : Vec<String> = vec![];
+	let mut positions : Vec<(Orientation, dim, dim)> = vec![];
 	
-	let add_word = |dic: &mut Vec<_>, line: &ArrayView<_, _>, from, to| {
+	let mut add_word = |dic: &mut Vec<_>, orientation: Orientation, i, line: &ArrayView<char, _>, from, to| {
 		if to - from > 1 {
-			let v : Vec<u8> = line.iter()
+			let v : String = line.iter()
 				.skip(from)
 				.take(to-from)
 				.cloned()
 				.collect();
 			dic.push(v);
+			let (y, x) = orientation.align(i, from);
+			positions.push((orientation, y, x));
 		}
 	};
 	
@@ -82,8 +369,8 @@ fn gen_problem(templ: &mut OwnedArray<u8, MatrixDim>) -> Problem {
 			let mut run_start = 0;
 			for j in 0 .. *line.dim() {
 				match line[LineDim(j)] {
-					b'#'|b'*' => {
-						add_word(&mut dic, &line, run_start, j);
+					'#'|'*' => {
+						add_word(&mut dic, orientation, i, &line, run_start, j);
 						run_start = j+1;
 					},
 					_ => {
@@ -91,7 +378,7 @@ fn gen_problem(templ: &mut OwnedArray<u8, MatrixDim>) -> Problem {
 				}
 			}
 			
-			add_word(&mut dic, &line, run_start, *line.dim());
+			add_word(&mut dic, orientation, i, &line, run_start, *line.dim());
 		}
 	}
 	
@@ -99,30 +386,39 @@ fn gen_problem(templ: &mut OwnedArray<u8, MatrixDim>) -> Problem {
 		for i in 0 .. templ.dim()[1] {
 			let idx = MatrixDim(j, i);
 			match templ[idx] {
-				b'#' => board[idx] = false,
-				b'*' | _ => board[idx] = true
+				'#' => board[idx] = false,
+				_ => board[idx] = true
 			}
 		}
 	}
 	
-	Problem::new(dic, board)
+	// word i of the dictionary sits at positions[i], if it is one of the runs
+	let mut order: Vec<usize> = (0..dic.len() + extra.len()).collect();
+	if shuffle {
+		for i in 0..order.len() {
+			let j = rng.gen_usize(Range::new(i, order.len()));
+			order.swap(i, j);
+		}
+	}
+	dic.extend(extra);
+	let dic: Vec<String> = order.iter().map(|&i| dic[i].clone()).collect();
+	
+	let problem = Problem::new(dic, board);
+	let mut placements: Vec<Placement> = order.iter().enumerate()
+		.filter(|&(_, &i)| i < positions.len())
+		.map(|(word_id, &i)| {
+			let (orientation, y, x) = positions[i];
+			Placement::new(i, orientation, y, x, problem.dic[word_id].clone())
+		})
+		.collect();
+	placements.sort_by_key(|place| place.id);
+	
+	(problem, placements)
 }
 
-fn write_problem(problem: &Problem) {
-	let path = Path::new("out_problem.xword");
-    let display = path.display();
-    
-    let mut options = OpenOptions::new();
-	options.create(true).write(true).truncate(true);
-
-
-    let mut file = match options.open(&path) {
-        Err(why) => panic!("couldn't open {}: {}", display,
-                                                   Error::description(&why)),
-        Ok(file) => file,
-    };
-	
-	let mut content = vec![];
+/// Writes the problem in xword's format, after the `header` comment lines.
+fn write_problem(file: &str, problem: &Problem, header: &str) {
+	let mut content = header.as_bytes().to_vec();
 	let dim = problem.board.dim();
 	let dim_str = format!("{}x{}", dim[0], dim[1]);
 	content.extend(dim_str.as_bytes());
@@ -141,52 +437,67 @@ fn write_problem(problem: &Problem) {
 	content.extend(b"\n-----\n");
 	
 	for word in problem.dic.iter() {
-		let str : &[u8] = &*word.str;
-		content.extend(str);
+		content.extend(word.to_string().as_bytes());
 		content.push(b'\n');
 	}
 	
-	match file.write_all(&content) {
-		Err(why) => panic!("couldn't write {}: {}", display, Error::description(&why)),
-		Ok(()) => (),
+	write_file(file, &content);
+}
+
+fn write_file(file: &str, content: &[u8]) {
+	let path = Path::new(file);
+    let display = path.display();
+    
+    let mut options = OpenOptions::new();
+	options.create(true).write(true).truncate(true);
+
+
+    let mut file = match options.open(path) {
+        Err(why) => panic!("couldn't open {}: {}", display, why),
+        Ok(file) => file,
+    };
+	
+	if let Err(why) = file.write_all(content) {
+		panic!("couldn't write {}: {}", display, why);
 	}
 }
 
-fn read_template() -> Vec<u8> {
-	let path = Path::new("template.xtempl");
+fn read_file(file: &str) -> Vec<u8> {
+	let path = Path::new(file);
     let display = path.display();
 
-    let mut file = match File::open(&path) {
-        Err(why) => panic!("couldn't open {}: {}", display,
-                                                   Error::description(&why)),
+    let mut file = match File::open(path) {
+        Err(why) => panic!("couldn't open {}: {}", display, why),
         Ok(file) => file,
     };
 
     let mut bytes : Vec<u8> = vec![];
     match file.read_to_end(&mut bytes) {
-        Err(why) => panic!("couldn't read {}: {}", display,
-                                                   Error::description(&why)),
+        Err(why) => panic!("couldn't read {}: {}", display, why),
         Ok(_) => ()
     }
     
     bytes
 }
 
-fn parse(bytes: Vec<u8>) -> OwnedArray<u8, MatrixDim> {
-	let re = Regex::new(r"^(?m)(\d{1,3})x(\d{1,3})[\r\n]{1,2}((?:[_#\*]+[\r\n]{1,2})+)[\r\n]*$").unwrap();
-	let caps = re.captures(&bytes).unwrap();
+fn parse(bytes: Vec<u8>) -> Array<char, MatrixDim> {
+	let text = String::from_utf8(bytes).expect("the template must be UTF-8");
+	let re = Regex::new(r"^(?m)(\d{1,3})x(\d{1,3})[\r\n]{1,2}((?:[_#\*\p{L}]+[\r\n]{1,2})+)[\r\n]*$").unwrap();
+	let caps = re.captures(&text).unwrap();
 	
-	let h = str::from_utf8(caps.at(1).unwrap()).unwrap().parse::<dim>().unwrap();
-	let w = str::from_utf8(caps.at(2).unwrap()).unwrap().parse::<dim>().unwrap();
+	let h = caps.at(1).unwrap().parse::<dim>().unwrap();
+	let w = caps.at(2).unwrap().parse::<dim>().unwrap();
 	let board_str = caps.at(3).unwrap();
 	
-	let mut board: OwnedArray<u8, MatrixDim> = OwnedArray::default(MatrixDim(h, w));
-	let re = Regex::new(r"(?m)\n?(^[_#\*]+)").unwrap();
+	let mut board: Array<char, MatrixDim> = Array::from_elem(MatrixDim(h, w), '#');
+	let re = Regex::new(r"(?m)\n?(^[_#\*\p{L}]+)").unwrap();
     for (j, cap) in re.captures_iter(board_str).enumerate() {
-    	for (i, &c) in cap.at(1).unwrap().iter().enumerate() {
+    	for (i, c) in cap.at(1).unwrap().chars().enumerate() {
     		let (j, i) = (j as dim, i as dim);
     		match c {
-    			b'_' | b'#' | b'*' => board[MatrixDim(j, i)] = c,
+    			'_' | '#' | '*' => board[MatrixDim(j, i)] = c,
+    			// a letter of an already filled grid
+    			_ if c.is_alphabetic() => board[MatrixDim(j, i)] = c.to_lowercase().next().unwrap(),
     			_    => panic!("unexpected char: {}", c)
     		}
     	}
@@ -194,3 +505,18 @@ fn parse(bytes: Vec<u8>) -> OwnedArray<u8, MatrixDim> {
     
     board
 }
+
+
+struct Opts {
+	template: String,
+	output: String,
+	solution: String,
+	seed: Option<u32>,
+	generate: Option<TemplateParams>, // replaces the template file
+	words: Option<Vec<String>>,
+	distractors: usize,
+	decoys: usize,
+	shuffle: bool,
+	letters: LetterDist,
+	rules: Option<(Preset, bool)>, // with --word-runs; picked from the reference layout if not given
+}
@@ -6,12 +6,22 @@ use rand::{SeedableRng, XorShiftRng, thread_rng};
 use rand::distributions::{IndependentSample, Range};
 use global2::data::ScoredMove;
 use global2::weighted_selection_tree::Key;
-use global2::sliced_arena::SlicedArena;
-use std::mem;
-
-#[derive(Clone, Debug, Copy)]
-#[repr(simd)]
-pub struct Align64 { al1: u64, al2: u64 }
+use dictionary::{self, DicEntry};
+use std::cell::UnsafeCell;
+use std::sync::Arc;
+use std::fmt::{self, Write};
+
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "nightly", repr(simd))]
+#[cfg_attr(not(feature = "nightly"), repr(C, align(16)))]
+pub struct Align64([u64; 2]);
+
+// SIMD types may not be projected into, so Debug cannot be derived
+impl fmt::Debug for Align64 {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Align64")
+	}
+}
 
 
 
@@ -96,34 +106,97 @@ impl Deref for LineDim {
 //---- Word ----------------------------------------------------------------------------
 pub type WordId = usize;
 
+/// A dictionary word. Its letters and dictionary entry are reference counted, so words and the placements holding them
+/// can be cloned cheaply and stay valid after the `Problem` they came from is dropped.
 #[derive(Clone, Debug)]
 pub struct Word {
 	pub id: WordId, // unique id
-	pub str: &'static [u8],
+	data: Arc<WordData>,
+}
+
+// kept behind a single Arc so that cloning a word, and every placement holding it, costs one reference count
+#[derive(Debug)]
+struct WordData {
+	str: Box<[char]>, // one Unicode scalar per grid cell
+	entry: DicEntry, // the entry as written and its clues
 }
 
 impl Word {
-	pub fn new(id: WordId, str: &'static [u8]) -> Word {
-		Word { id:id, str:str }
+	pub fn new(id: WordId, str: &[char]) -> Word {
+		Word::with_entry(id, DicEntry::new(str.iter().collect()))
+	}
+	
+	pub fn with_entry(id: WordId, entry: DicEntry) -> Word {
+		let str: Vec<char> = entry.word.chars().collect();
+		Word { id, data:Arc::new(WordData { str:str.into_boxed_slice(), entry }) }
+	}
+	
+	/// The grid letters, one Unicode scalar per cell.
+	#[inline]
+	pub fn str(&self) -> &[char] {
+		&self.data.str
+	}
+	
+	/// The dictionary entry the word was made from.
+	#[inline]
+	pub fn entry(&self) -> &DicEntry {
+		&self.data.entry
+	}
+	
+	/// The word as it should appear in clue lists, e.g. "rock 'n' roll" rather than `rocknroll`.
+	pub fn display_form(&self) -> String {
+		if self.entry().display.is_empty() { self.to_string() } else { self.entry().display.clone() }
+	}
+	
+	/// The letter counts of the display form, e.g. "(5,5,9)".
+	pub fn enumeration(&self) -> String {
+		dictionary::enumeration(&self.display_form())
+	}
+	
+	#[inline]
+	pub fn clues(&self) -> &[String] {
+		&self.entry().clues
+	}
+	
+	/// The clue to publish with the word, if it has any.
+	#[inline]
+	pub fn clue(&self) -> Option<&str> {
+		self.entry().clues.first().map(|clue| &**clue)
 	}
 	
 	#[inline]
 	pub fn len(&self) -> dim {
-		self.str.len() as dim
+		self.str().len() as dim
 	}
 }
 
 impl ::std::ops::Index<dim> for Word {
-	type Output = u8;
+	type Output = char;
 	
 	#[inline]
     fn index(&self, index: dim) -> &Self::Output {
-    	&self.str[index as usize]
+    	&self.str()[index]
     }
 }
 
+impl fmt::Display for Word {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for &c in self.str() {
+			f.write_char(c)?;
+		}
+		Ok(())
+	}
+}
+
+/// Builds a word without a `Problem`, for tests.
+#[cfg(test)]
+pub fn test_word(id: WordId, str: &str) -> Word {
+	let chars: Vec<char> = str.chars().collect();
+	Word::new(id, &chars)
+}
+
 //---- Orientation ---------------------------------------------------------------------
-#[derive(PartialEq, Clone, Copy, Hash, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
 pub enum Orientation { 
 	VER = 0, 
 	HOR = 1
@@ -157,6 +230,27 @@ impl Orientation {
 }
 
 
+//---- Symmetry ------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+	/// The grid looks the same after a half turn, like most published crosswords.
+	Rotational,
+	/// The left half mirrors the right half.
+	Mirror,
+	None,
+}
+
+impl Symmetry {
+	/// The cell of an `h x w` grid that must match (y, x).
+	pub fn mirror(self, MatrixDim(h, w): MatrixDim, y: dim, x: dim) -> (dim, dim) {
+		match self {
+			Symmetry::Rotational => (h-1-y, w-1-x),
+			Symmetry::Mirror => (y, w-1-x),
+			Symmetry::None => (y, x),
+		}
+	}
+}
+
 //---- Placement -----------------------------------------------------------------------
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct PlacementId(pub usize);
@@ -336,32 +430,29 @@ impl AsRef<Placement> for Placement {
 //---- Problem -------------------------------------------------------------------------
 
 pub struct Problem {
-    dic_arena: SlicedArena<u8>,
 	pub dic: Vec<Word>,
 	pub board: Array<bool, MatrixDim>,
 }
 
 impl Problem {
-	pub fn new(dic: Vec<Vec<u8>>, board: Array<bool, MatrixDim>) -> Problem {
-    	let (dic, dic_arena) = Self::dic_arena(dic);
-		Problem { dic:dic, dic_arena:dic_arena, board:board }
+	pub fn new(dic: Vec<String>, board: Array<bool, MatrixDim>) -> Problem {
+		Self::from_entries(dic.into_iter().map(DicEntry::new).collect(), board)
 	}
 	
-    fn dic_arena(dic: Vec<Vec<u8>>) -> (Vec<Word>, SlicedArena<u8>) {
-    	let word_lens: Vec<usize> = dic.iter().map(|wordv| wordv.len()).collect();
-    	let mut dic_arena: SlicedArena<u8> = SlicedArena::new(&word_lens);
-    	let mut dicw: Vec<Word> = Vec::with_capacity(dic.len());
-    	for (i, wordv) in dic.into_iter().enumerate() {
-    	    {
-        	    let slice: &mut [u8] = dic_arena.slice_mut(i);
-        	    slice.clone_from_slice(&wordv);
-    	    }
-    	    let slice: &'static [u8] = unsafe { mem::transmute(dic_arena.slice(i)) };
-    	    dicw.push(Word::new(i, slice));
-    	}
-    	
-    	(dicw, dic_arena)
-    }
+	/// `dic[i]` is built from `entries[i]`, carrying along its display form and clues.
+	pub fn from_entries(entries: Vec<DicEntry>, board: Array<bool, MatrixDim>) -> Problem {
+		let dic = entries.into_iter().enumerate().map(|(i, entry)| Word::with_entry(i, entry)).collect();
+		Problem { dic, board }
+	}
+	
+	/// The dictionary entries of the words, in `dic` order.
+	pub fn entries(&self) -> Vec<DicEntry> {
+		self.dic.iter().map(|word| word.entry().clone()).collect()
+	}
+	
+	pub fn has_clues(&self) -> bool {
+		self.dic.iter().any(|word| !word.clues().is_empty())
+	}
 }
 
 
@@ -429,19 +520,24 @@ impl AbstractRng for TLRng {
 }
 
 
-#[derive(Clone)]
-struct XORRng(XorShiftRng);
+// the generator is mutated through a shared reference, as AbstractRng's methods take &self
+struct XORRng(UnsafeCell<XorShiftRng>);
 
 impl XORRng {
     #[inline]
-    #[allow(mutable_transmutes)]
     fn rng(&self) -> &mut XorShiftRng {
-//        let rng: &mut XorShiftRng = unsafe { mem::transmute(&self.0) };
-        unsafe { mem::transmute(&self.0) }
+        // XORRng is not Sync and the returned reference never outlives a single sample
+        unsafe { &mut *self.0.get() }
     }
     
 }
 
+impl Clone for XORRng {
+    fn clone(&self) -> XORRng {
+        XORRng(UnsafeCell::new(self.rng().clone()))
+    }
+}
+
 impl AbstractRng for XORRng {
 	#[inline]
 	fn clone_to_box(&self) -> Box<AbstractRng> {
@@ -469,7 +565,7 @@ impl AbstractRng for XORRng {
 
 pub fn xor_rng() -> Box<AbstractRng> {
 	let seed: [u32;4] = [27, 81, 3, 555];
-    Box::new(XORRng(XorShiftRng::from_seed(seed)))
+    Box::new(XORRng(UnsafeCell::new(XorShiftRng::from_seed(seed))))
 }
 
 pub fn tl_rng() -> Box<AbstractRng> {
@@ -479,8 +575,14 @@ pub fn tl_rng() -> Box<AbstractRng> {
 pub fn make_rng() -> Box<AbstractRng> {
 //	Box::new(XRng(XorShiftRng::from_seed(seed)))
 //	Box::new(TLRng)
-	let seed: [u32;4] = [27, 81, 3, 555];
-    Box::new(XORRng(XorShiftRng::from_seed(seed)))
+	seeded_rng(0)
+}
+
+/// A reproducible generator for the given seed; seed 0 gives the same sequence as `make_rng`.
+pub fn seeded_rng(seed: u32) -> Box<dyn AbstractRng> {
+	let mix = |salt: u32| seed.wrapping_mul(0x9E37_79B9).rotate_left(salt);
+	let seed: [u32;4] = [27 ^ mix(0), 81 ^ mix(8), 3 ^ mix(16), 555 ^ mix(24)];
+    Box::new(XORRng(UnsafeCell::new(XorShiftRng::from_seed(seed))))
 }
 
 
@@ -544,18 +646,33 @@ mod placement_tests {
     use super::*;
 	use super::Orientation::*;
 
-	fn word(wid: WordId, str: &'static [u8]) -> Word {
-		Word::new(wid, str)
+	fn word(wid: WordId, str: &str) -> Word {
+		test_word(wid, str)
 	}
 	
 	fn place(pid: usize, or: Orientation, y: dim, x: dim, word: Word) -> Placement {
 		Placement::new(pid, or, y, x, word)
 	}
 	
+    #[test]
+    fn words_outlive_problem() {
+    	let board = Array::from_elem(MatrixDim(1, 3), true);
+    	let place = {
+    		let mut entry = DicEntry::from_display("Ab-c", false);
+    		entry.clues = vec!["First three".to_string()];
+    		let problem = Problem::from_entries(vec![entry], board);
+    		place(0, HOR, 0, 0, problem.dic[0].clone())
+    	};
+    	assert_eq!(place.word.to_string(), "abc");
+    	assert_eq!(place.word.display_form(), "Ab-c");
+    	assert_eq!(place.word.clues(), &["First three".to_string()]);
+    	assert_eq!(place.word.clue(), Some("First three"));
+    }
+	
     #[test]
     fn incompat_overlap() {
-    	let word1 = Word::new(0, b"abc");
-    	let word2 = Word::new(1, b"ab");
+    	let word1 = word(0, "abc");
+    	let word2 = word(1, "ab");
     	let p1 = Placement::new(0, Orientation::HOR, 0, 0, word1);
     	let p2 = Placement::new(1, Orientation::HOR, 0, 0, word2);
     	
@@ -565,8 +682,8 @@ mod placement_tests {
 	
     #[test]
     fn incompat_adjacent() {
-    	let word1 = Word::new(0, b"abc");
-    	let word2 = Word::new(1, b"ab");
+    	let word1 = word(0, "abc");
+    	let word2 = word(1, "ab");
     	let p1 = Placement::new(0, Orientation::HOR, 0, 0, word1);
     	let p2 = Placement::new(1, Orientation::VER, 1, 2, word2);
     	
@@ -576,8 +693,8 @@ mod placement_tests {
 	
     #[test]
     fn incompat_intersection() {
-    	let word1 = Word::new(0, b"abc");
-    	let word2 = Word::new(1, b"bb");
+    	let word1 = word(0, "abc");
+    	let word2 = word(1, "bb");
     	let p1 = Placement::new(0, Orientation::HOR, 0, 0, word1);
     	let p2 = Placement::new(1, Orientation::VER, 0, 0, word2);
     	
@@ -591,8 +708,8 @@ mod placement_tests {
 //    	let place = |pid, or, y, x, word| Placement::new(pid, or, y, x, word);
     	
     	{
-	    	let word1 = word(0, b"abc");
-	    	let word2 = word(1, b"ab");
+	    	let word1 = word(0, "abc");
+	    	let word2 = word(1, "ab");
 	    	let p1 = place(0, HOR, 0, 0, word1);
 	    	let p2 = place(1, VER, 0, 0, word2);
 	    	
@@ -601,8 +718,8 @@ mod placement_tests {
     	}
     	
     	{
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"ab");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "ab");
 	    	let p1 = place(0, HOR, 1, 0, word1);
 	    	let p2 = place(1, VER, 0, 0, word2);
 	    	
@@ -611,8 +728,8 @@ mod placement_tests {
     	}
     	
     	{
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"ab");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "ab");
 	    	let p1 = place(0, HOR, 1, 0, word1);
 	    	let p2 = place(1, VER, 0, 0, word2);
 	    	
@@ -621,11 +738,28 @@ mod placement_tests {
     	}
     }
     
+    #[test]
+    fn compat_intersection_non_latin() {
+    	let word1 = word(0, "шар");
+    	let word2 = word(1, "рот");
+    	let word3 = word(2, "סוד");
+    	
+    	// "шар" and "рот" share the 'р'
+    	let p1 = place(0, HOR, 0, 0, word1.clone());
+    	let p2 = place(1, VER, 0, 2, word2);
+    	assert!(p1.compatible(&p2));
+    	assert!(p2.compatible(&p1));
+    	
+    	let p3 = place(2, VER, 0, 2, word3);
+    	assert!(!p1.compatible(&p3));
+    	assert!(!p3.compatible(&p1));
+    }
+    
     #[test]
     fn compat_corners() {
     	{  // upper left corner VH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, VER, 0, 1, word2);
 	    	
@@ -634,8 +768,8 @@ mod placement_tests {
     	}
     	
     	{  // upper left corner HH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, HOR, 1, 0, word2);
 	    	
@@ -644,8 +778,8 @@ mod placement_tests {
     	}
     	
     	{  // lower left corner VH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, VER, 3, 1, word2);
 	    	
@@ -654,8 +788,8 @@ mod placement_tests {
     	}
     	
     	{  // lower left corner HH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, HOR, 3, 0, word2);
 	    	
@@ -664,8 +798,8 @@ mod placement_tests {
     	}
     	
     	{  // upper right corner VH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, VER, 0, 4, word2);
 	    	
@@ -674,8 +808,8 @@ mod placement_tests {
     	}
     	
     	{  // upper right corner HH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, HOR, 1, 4, word2);
 	    	
@@ -684,8 +818,8 @@ mod placement_tests {
     	}
     	
     	{  // lower right corner VH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, VER, 3, 4, word2);
 	    	
@@ -694,8 +828,8 @@ mod placement_tests {
     	}
     	
     	{  // lower right corner HH
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, HOR, 2, 2, word1);
 	    	let p2 = place(1, HOR, 3, 4, word2);
 	    	
@@ -704,8 +838,8 @@ mod placement_tests {
     	}
     	
     	{  // lower right corner VV
-	    	let word1 = word(0, b"bc");
-	    	let word2 = word(1, b"de");
+	    	let word1 = word(0, "bc");
+	    	let word2 = word(1, "de");
 	    	let p1 = place(0, VER, 2, 2, word1);
 	    	let p2 = place(1, VER, 4, 3, word2);
 	    	
@@ -0,0 +1,242 @@
+use rand::distributions::Range;
+use common::AbstractRng;
+
+
+//---- DicEntry ------------------------------------------------------------------------
+/// A dictionary entry: the letters that go into the grid, the entry as written (e.g. "rock 'n' roll" for `rocknroll`)
+/// and its clues.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DicEntry {
+	pub word: String,
+	pub display: String,
+	pub clues: Vec<String>,
+}
+
+impl DicEntry {
+	/// An entry whose display form is the grid word itself.
+	pub fn new(word: String) -> DicEntry {
+		DicEntry { display: word.clone(), word, clues: vec![] }
+	}
+
+	/// An entry written as `display`, which is normalized into grid letters.
+	pub fn from_display(display: &str, fold_accents: bool) -> DicEntry {
+		DicEntry { word: normalize(display, fold_accents), display: display.to_string(), clues: vec![] }
+	}
+}
+
+
+/// Turns an entry as written into grid letters: spaces and punctuation are dropped and letters are lowercased, so that
+/// "Rock 'n' Roll" becomes `rocknroll`. With `fold_accents`, accented Latin letters lose their marks ("Café" -> `cafe`).
+/// Every letter takes exactly one cell.
+pub fn normalize(display: &str, fold_accents: bool) -> String {
+	display.chars()
+		.filter(|&c| c.is_alphabetic())
+		.map(lowercase)
+		.map(|c| if fold_accents { fold_accent(c) } else { c })
+		.collect()
+}
+
+
+/// The lowercase form of a letter as a single character. Where lowercasing gives several characters, e.g. "i\u{307}" for
+/// 'İ', the combining marks after the letter are dropped.
+pub fn lowercase(c: char) -> char {
+	c.to_lowercase().next().unwrap_or(c)
+}
+
+
+/// The enumeration of an entry as printed after its clue: letter counts of the words, separated by `,` (or `-` for
+/// hyphenated parts), e.g. "(5,5,9)" for "Great Smoky Mountains" and "(4-1-4)" for "will-o'-wisp".
+pub fn enumeration(display: &str) -> String {
+	let mut out = String::from("(");
+	let mut count = 0;
+	let mut sep = None;
+	for c in display.chars() {
+		if c.is_alphabetic() {
+			if count == 0 {
+				if let Some(sep) = sep.take() {
+					out.push(sep);
+				}
+			}
+			count += 1;
+		} else if c == ' ' || c == '-' {
+			if count > 0 {
+				out.push_str(&count.to_string());
+				count = 0;
+				sep = Some(if c == '-' { '-' } else { ',' });
+			} else if c == '-' && sep.is_some() {
+				sep = Some('-');
+			}
+		}
+	}
+	if count > 0 {
+		out.push_str(&count.to_string());
+	}
+	out.push(')');
+	out
+}
+
+
+const ACCENTED: &[(&str, char)] = &[
+	("àáâãäåāăą", 'a'), ("çćĉċč", 'c'), ("ďđ", 'd'), ("èéêëēĕėęě", 'e'), ("ĝğġģ", 'g'), ("ĥħ", 'h'),
+	("ìíîïĩīĭįı", 'i'), ("ĵ", 'j'), ("ķ", 'k'), ("ĺļľŀł", 'l'), ("ñńņňŉ", 'n'), ("òóôõöøōŏő", 'o'), ("ŕŗř", 'r'),
+	("śŝşšș", 's'), ("ţťŧț", 't'), ("ùúûüũūŭůűų", 'u'), ("ŵ", 'w'), ("ýÿŷ", 'y'), ("źżž", 'z'),
+];
+
+/// Strips the diacritic from a lowercase Latin letter; other characters are returned as they are.
+pub fn fold_accent(c: char) -> char {
+	if c.is_ascii() {
+		return c;
+	}
+	ACCENTED.iter().find(|&&(from, _)| from.contains(c)).map_or(c, |&(_, to)| to)
+}
+
+
+//---- ScoredWord ----------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoredWord {
+	pub word: String,
+	pub score: Option<i32>,
+}
+
+impl ScoredWord {
+	/// Length in grid cells, i.e. not counting spaces and punctuation.
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.word.chars().filter(|c| c.is_alphabetic()).count()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+
+/// Parses a UTF-8 word list with one entry per line, either a bare word or a word in the common `word;score` format. Words
+/// are kept as written, see `normalize`; lines that do not hold a valid word (letters of any alphabet, spaces and `'`, `,`,
+/// `!`, `-`) are skipped.
+pub fn parse_word_list(bytes: &[u8]) -> Vec<ScoredWord> {
+	let text = String::from_utf8_lossy(bytes);
+	let mut words = vec![];
+	for line in text.lines() {
+		let mut fields = line.trim().splitn(2, ';');
+		let word = fields.next().unwrap().trim().to_string();
+		if !word.chars().any(|c| c.is_alphabetic()) || !word.chars().all(valid_char) {
+			continue;
+		}
+
+		let score = match fields.next() {
+			None => None,
+			Some(score) => match score.trim().parse::<i32>() {
+				Ok(score) => Some(score),
+				Err(_) => continue,
+			}
+		};
+
+		words.push(ScoredWord { word, score });
+	}
+
+	words
+}
+
+
+/// Whether `c` may appear in a dictionary word.
+pub fn valid_char(c: char) -> bool {
+	c.is_alphabetic() || "' ,!-".contains(c)
+}
+
+
+//---- WordListFilter ------------------------------------------------------------------
+#[derive(Clone, Debug, Default)]
+pub struct WordListFilter {
+	/// Drop words scored below this; words without a score are always kept.
+	pub min_score: Option<i32>,
+	pub min_len: Option<usize>,
+	pub max_len: Option<usize>,
+}
+
+impl WordListFilter {
+	pub fn apply(&self, words: Vec<ScoredWord>) -> Vec<ScoredWord> {
+		words.into_iter().filter(|w|
+			self.min_score.is_none_or(|min| w.score.is_none_or(|score| score >= min)) &&
+			self.min_len.is_none_or(|min| w.len() >= min) &&
+			self.max_len.is_none_or(|max| w.len() <= max)
+		).collect()
+	}
+}
+
+
+/// Picks `n` of the words uniformly at random, keeping their original order.
+pub fn sample<T>(items: Vec<T>, n: usize, rng: &dyn AbstractRng) -> Vec<T> {
+	if n >= items.len() {
+		return items;
+	}
+
+	// partial Fisher-Yates over the indices
+	let mut indices: Vec<usize> = (0..items.len()).collect();
+	for i in 0..n {
+		let j = rng.gen_usize(Range::new(i, indices.len()));
+		indices.swap(i, j);
+	}
+	indices.truncate(n);
+	indices.sort();
+
+	let mut keep = vec![false; items.len()];
+	for i in indices {
+		keep[i] = true;
+	}
+	items.into_iter().zip(keep).filter_map(|(item, keep)| if keep { Some(item) } else { None }).collect()
+}
+
+
+
+#[cfg(test)]
+mod dictionary_tests {
+	use super::*;
+	use common::make_rng;
+
+	fn scored(word: &str, score: Option<i32>) -> ScoredWord {
+		ScoredWord { word: word.to_string(), score }
+	}
+
+	#[test]
+	fn parses_plain_and_scored_lists() {
+		let words = parse_word_list("APPLE;50\r\npear\n\nbad;x\nfig ; 20\nc3po\nÉTÉ;30\nМОСКВА\n".as_bytes());
+		assert_eq!(words, vec![scored("APPLE", Some(50)), scored("pear", None), scored("fig", Some(20)),
+							   scored("ÉTÉ", Some(30)), scored("МОСКВА", None)]);
+	}
+
+	#[test]
+	fn normalizes_display_forms() {
+		assert_eq!(normalize("Rock 'n' Roll!", false), "rocknroll");
+		assert_eq!(normalize("Crème brûlée", false), "crèmebrûlée");
+		assert_eq!(normalize("Crème brûlée", true), "cremebrulee");
+		assert_eq!(normalize("Łódź", true), "lodz");
+		assert_eq!(normalize("Москва", true), "москва");
+		assert_eq!(normalize("İstanbul", false), "istanbul");
+		assert_eq!(normalize("İstanbul", false).chars().count(), scored("İstanbul", None).len());
+
+		assert_eq!(enumeration("Great Smoky Mountains"), "(5,5,9)");
+		assert_eq!(enumeration("will-o'-wisp"), "(4-1-4)");
+		assert_eq!(enumeration("rock 'n' roll"), "(4,1,4)");
+		assert_eq!(enumeration("apple"), "(5)");
+		assert_eq!(scored("ice cream", None).len(), 8);
+	}
+
+	#[test]
+	fn filters_by_score_and_length() {
+		let words = vec![scored("apple", Some(50)), scored("pear", None), scored("fig", Some(20)), scored("kiwi", Some(10)),
+						 scored("שלום", Some(30))];
+		let filter = WordListFilter { min_score: Some(20), min_len: Some(4), max_len: Some(4) };
+		assert_eq!(filter.apply(words), vec![scored("pear", None), scored("שלום", Some(30))]);
+	}
+
+	#[test]
+	fn samples_keep_order() {
+		let rng = make_rng();
+		let sampled = sample((0..100).collect::<Vec<_>>(), 10, &*rng);
+		assert_eq!(sampled.len(), 10);
+		assert!(sampled.windows(2).all(|w| w[0] < w[1]));
+		assert_eq!(sample(vec![1, 2, 3], 5, &*rng), vec![1, 2, 3]);
+	}
+}
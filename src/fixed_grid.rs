@@ -2,6 +2,7 @@ use std::ops::Deref;
 use std::collections::{HashMap, HashSet};
 use ndarray::Array;
 use common::{dim, Placement, PlacementId, MatrixDim, Orientation, AbstractRng};
+use rules::{self, LayoutRules};
 
 use rand::distributions::Range;
 
@@ -52,13 +53,14 @@ pub struct FixedGrid<'a, Move: PlaceMove> {
 	pub field: Array<Vec<PlacementId>, MatrixDim>, // TODO: make the vecs constant size 2
 	pub moves: HashMap<PlacementId, FixedGridMove<Move>>,
 	rng: &'a AbstractRng,
+	rules: &'a dyn LayoutRules,
 	counter: usize
 }
 
 impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
     #[inline(never)]
-	pub fn new(h: dim, w: dim, rng: &'a AbstractRng) -> FixedGrid<'a, Move> {
-		FixedGrid { field: Array::default(MatrixDim(h, w)), moves: HashMap::new(), rng:rng, counter:0 }
+	pub fn new(h: dim, w: dim, rng: &'a dyn AbstractRng, rules: &'a dyn LayoutRules) -> FixedGrid<'a, Move> {
+		FixedGrid { field: Array::default(MatrixDim(h, w)), moves: HashMap::new(), rng, rules, counter:0 }
 	}
 	
 	pub fn place_all(&mut self, seq: Vec<Move>) {
@@ -116,7 +118,14 @@ impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
 	}
 	
 	
+	/// The letters of the placement that touch another word's letter side by side without being crossed, unless the rules
+	/// accept the run of letters they make.
 	pub fn adjacencies_of(&self, id: PlacementId) -> Vec<AdjacencyInfo> {
+		// the rules keep side by side letters apart, there is nothing to resolve
+		if !self.rules.side_contacts() {
+			return vec![];
+		}
+		
 		let place: &Placement = &(self.moves[&id].mv.place());
 		
 		let perp_or = place.orientation.perp_orientation();
@@ -136,8 +145,8 @@ impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
 			let (y1, x1) = (y1 as dim, x1 as dim);
 			let (y2, x2) = (y2 as dim, x2 as dim);
 			
-			if self.count_words_at(y1, x1) == 1 ||
-    		   self.count_words_at(y2, x2) == 1 {
+			if (self.count_words_at(y1, x1) == 1 || self.count_words_at(y2, x2) == 1) &&
+			   !self.rules.accepts_run(&rules::run_through(y, x, perp.0, perp.1, |y, x| self.letter_at(y, x))) {
 		       // we currently unify the two cases; if we need to disambiguate in future, we can add another field to AdjacencyInfo 
 				let adj = AdjacencyInfo { y:y, x:x, or:perp_or };
 				adjacencies.push(adj); 
@@ -221,10 +230,15 @@ impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
 					out.into_iter()
 			}).collect();
 			
-			let suspects = adjacencies.into_iter()
-								  	  .filter(|dep| self.moves.contains_key(&dep))
-								      .map(|adj| &self.moves[&adj]);
-			adjacencies = self.find_adjacencies(suspects);
+			if self.rules.side_contacts() {
+				// a deleted word may leave any of its neighbours with uncrossed letters or runs that are no longer words
+				adjacencies = self.find_adjacencies(self.moves.values());
+			} else {
+				let suspects = adjacencies.into_iter()
+									  	  .filter(|dep| self.moves.contains_key(dep))
+									      .map(|adj| &self.moves[&adj]);
+				adjacencies = self.find_adjacencies(suspects);
+			}
 		}
 		
 		let eff = self.efficiency();
@@ -262,6 +276,14 @@ impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
 		adjacencies
 	}
 	
+	fn letter_at(&self, y: dim, x: dim) -> Option<char> {
+		let words_opt = self.field.get(MatrixDim(y, x)) as Option<&Vec<PlacementId>>;
+		words_opt.and_then(|words| words.first()).map(|id| {
+			let place = self.moves[id].mv.place();
+			place.word[y - place.y + x - place.x]
+		})
+	}
+	
 	fn count_words_at(&self, y: dim, x: dim) -> usize {
 		let words_opt = self.field.get(MatrixDim(y, x)) as Option<&Vec<PlacementId>>;
 		words_opt.map_or(0, |words| words.len())
@@ -278,9 +300,9 @@ impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
 						
 						match plc.orientation {
 							Orientation::VER =>
-								print!("{}", plc.word.str[j - plc.y] as char),
+								print!("{}", plc.word.str()[j - plc.y]),
 							Orientation::HOR =>
-								print!("{}", plc.word.str[i - plc.x] as char),
+								print!("{}", plc.word.str()[i - plc.x]),
 						}
 				} else {
 								print!("_")
@@ -294,6 +316,21 @@ impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
 }
 
 
+/// The constructor's objective for a finished layout, i.e. `FixedGrid::efficiency` of the placements: 1 for every word that
+/// crosses at least one other word.
+pub fn objective(dim: MatrixDim, placements: &[Placement]) -> Eff {
+	let mut covers: Array<u8, MatrixDim> = Array::default(dim);
+	for place in placements {
+		place.fold_positions((), |_, y, x| covers[MatrixDim(y, x)] += 1);
+	}
+	
+	let crossing = placements.iter().filter(|place|
+		place.fold_positions(false, |crossed, y, x| crossed || covers[MatrixDim(y, x)] > 1)
+	).count();
+	Eff(crossing as eff_t)
+}
+
+
 
 //#[derive(Clone)]
 //struct IndexedMove<'a> {
@@ -332,3 +369,22 @@ impl<'a, Move: PlaceMove> FixedGrid<'a, Move> {
 //}
 //
 //impl<Move: AsRef<Placement>> Eq for Move {}
+
+
+
+#[cfg(test)]
+mod fixed_grid_tests {
+	use super::*;
+	use common::{test_word, Placement, MatrixDim};
+	use common::Orientation::*;
+
+	#[test]
+	fn objective_counts_crossing_words() {
+		let places = vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "abc")),
+			Placement::new(1, VER, 0, 2, test_word(1, "cd")),
+			Placement::new(2, HOR, 2, 0, test_word(2, "ef")),
+		];
+		assert_eq!(objective(MatrixDim(3, 3), &places), Eff(2));
+	}
+}
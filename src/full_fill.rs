@@ -0,0 +1,505 @@
+use std::collections::VecDeque;
+use fnv::FnvHashSet;
+use ndarray::Array;
+use rand::distributions::Range;
+use common::{dim, Word, Placement, MatrixDim, Symmetry, AbstractRng};
+use generator::{slots, is_connected, Slot};
+use render::letter_grid;
+
+
+//---- FillFailure ---------------------------------------------------------------------
+/// Why `full_fill` found no fill.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillFailure {
+	/// The slots no word could be found for, the most troublesome first.
+	pub slots: Vec<Slot>,
+	/// The search ran out of steps, so a fill may still exist.
+	pub gave_up: bool,
+}
+
+impl FillFailure {
+	pub fn describe(&self) -> String {
+		let slots: Vec<String> = self.slots.iter()
+			.map(|slot| format!("{:?} slot of {} letters at row {}, col {}", slot.orientation, slot.len, slot.y, slot.x))
+			.collect();
+		if self.gave_up {
+			format!("gave up searching; the hardest slots were:\n  {}", slots.join("\n  "))
+		} else {
+			format!("no dictionary words fit these slots along with the slots crossing them:\n  {}", slots.join("\n  "))
+		}
+	}
+}
+
+
+
+//---- full_fill -----------------------------------------------------------------------
+const FULL_FILL_STEPS: usize = 200000;
+
+/// Fills every slot of the board, i.e. every run of two or more open cells between blocks (or the border), with a distinct
+/// dictionary word of exactly its length. Candidate words are kept arc consistent across crossing slots, the slot with
+/// the fewest candidates is filled first, trying them in random order, and dead ends are backtracked. Returns one
+/// placement per slot, across and down words alike.
+pub fn full_fill(board: &Array<bool, MatrixDim>, dic: &[Word], rng: &dyn AbstractRng) -> Result<Vec<Placement>, FillFailure> {
+	fill_slots(board, &Array::default(board.dim()), dic, rng, FULL_FILL_STEPS)
+}
+
+
+/// Fills the slots of the board; a slot's word must have the `fixed` letters of its cells.
+pub fn fill_slots(board: &Array<bool, MatrixDim>, fixed: &Array<Option<char>, MatrixDim>, dic: &[Word], rng: &dyn AbstractRng,
+			  max_steps: usize) -> Result<Vec<Placement>, FillFailure> {
+	let slots = slots(&template(board));
+
+	// words are told apart by their letters
+	let mut seen = FnvHashSet::default();
+	let words: Vec<&Word> = dic.iter().filter(|word| seen.insert(word.str())).collect();
+
+	let fits = |word: &Word, slot: &Slot| word.len() == slot.len &&
+		slot.cells().into_iter().enumerate().all(|(pos, cell)| fixed[cell].is_none_or(|letter| word.str()[pos] == letter));
+	let domains: Vec<Vec<usize>> = slots.iter()
+		.map(|slot| (0..words.len()).filter(|&w| fits(words[w], slot)).collect())
+		.collect();
+	let empty: Vec<Slot> = slots.iter().zip(domains.iter()).filter(|&(_, domain)| domain.is_empty()).map(|(slot, _)| slot.clone()).collect();
+	if !empty.is_empty() {
+		return Err(FillFailure { slots: empty, gave_up: false });
+	}
+
+	let mut filler = FullFill { crossings: crossings(&slots, board.dim()), wipeouts: vec![0; slots.len()], slots,
+								words, steps: 0, max_steps };
+	let mut domains = domains;
+	let all: Vec<usize> = (0..filler.slots.len()).collect();
+	if let Err(s) = filler.propagate(&mut domains, all) {
+		return Err(FillFailure { slots: vec![filler.slots[s].clone()], gave_up: false });
+	}
+
+	let mut assigned = vec![false; filler.slots.len()];
+	match filler.search(domains, &mut assigned, rng) {
+		Some(domains) => Ok(filler.slots.iter().zip(domains.iter()).enumerate()
+			.map(|(i, (slot, domain))| Placement::new(i, slot.orientation, slot.y, slot.x, filler.words[domain[0]].clone()))
+			.collect()),
+		None => Err(filler.failure()),
+	}
+}
+
+
+/// Slot `other` crosses the slot at letter `pos`, at its own letter `other_pos`.
+struct Crossing {
+	other: usize,
+	pos: usize,
+	other_pos: usize,
+}
+
+fn crossings(slots: &[Slot], dim: MatrixDim) -> Vec<Vec<Crossing>> {
+	let mut owners: Array<Vec<(usize, usize)>, MatrixDim> = Array::default(dim);
+	for (s, slot) in slots.iter().enumerate() {
+		for (pos, cell) in slot.cells().into_iter().enumerate() {
+			owners[cell].push((s, pos));
+		}
+	}
+
+	let mut crossings: Vec<Vec<Crossing>> = slots.iter().map(|_| vec![]).collect();
+	for cell_owners in owners.iter() {
+		if cell_owners.len() == 2 {
+			let ((s, pos), (t, other_pos)) = (cell_owners[0], cell_owners[1]);
+			crossings[s].push(Crossing { other: t, pos, other_pos });
+			crossings[t].push(Crossing { other: s, pos: other_pos, other_pos: pos });
+		}
+	}
+	crossings
+}
+
+
+struct FullFill<'a> {
+	slots: Vec<Slot>,
+	words: Vec<&'a Word>,
+	crossings: Vec<Vec<Crossing>>, // parallel to `slots`
+	wipeouts: Vec<usize>, // how many times each slot ran out of candidates
+	steps: usize,
+	max_steps: usize,
+}
+
+impl<'a> FullFill<'a> {
+	fn search(&mut self, domains: Vec<Vec<usize>>, assigned: &mut [bool], rng: &dyn AbstractRng) -> Option<Vec<Vec<usize>>> {
+		self.steps += 1;
+		if self.steps > self.max_steps {
+			return None;
+		}
+
+		// the open slot with the fewest candidates, the most crossed one on a tie
+		let s = match (0..self.slots.len()).filter(|&s| !assigned[s])
+				.min_by_key(|&s| (domains[s].len(), !0 - self.crossings[s].len())) {
+			Some(s) => s,
+			None => return Some(domains),
+		};
+
+		let mut cands = domains[s].clone();
+		for i in 0..cands.len() {
+			let j = rng.gen_usize(Range::new(i, cands.len()));
+			cands.swap(i, j);
+		}
+
+		assigned[s] = true;
+		for w in cands {
+			let mut next = domains.clone();
+			next[s] = vec![w];
+
+			// no word is used twice
+			let mut changed = vec![s];
+			let mut wiped = false;
+			for t in 0..self.slots.len() {
+				if t != s && !assigned[t] && self.slots[t].len == self.slots[s].len {
+					let before = next[t].len();
+					next[t].retain(|&other| other != w);
+					if next[t].is_empty() {
+						self.wipeouts[t] += 1;
+						wiped = true;
+						break;
+					}
+					if next[t].len() != before {
+						changed.push(t);
+					}
+				}
+			}
+
+			if !wiped && self.propagate(&mut next, changed).is_ok() {
+				if let Some(filled) = self.search(next, assigned, rng) {
+					return Some(filled);
+				}
+			}
+			if self.steps > self.max_steps {
+				break;
+			}
+		}
+		assigned[s] = false;
+		None
+	}
+
+	/// Makes the candidates of every slot crossing a `changed` slot agree with the candidates there, and so on, until nothing
+	/// changes (AC-3). Fails with the slot that ran out of candidates.
+	fn propagate(&mut self, domains: &mut [Vec<usize>], changed: Vec<usize>) -> Result<(), usize> {
+		let mut queued = vec![false; self.slots.len()];
+		for &s in changed.iter() {
+			queued[s] = true;
+		}
+		let mut queue: VecDeque<usize> = changed.into_iter().collect();
+
+		while let Some(s) = queue.pop_front() {
+			queued[s] = false;
+			for crossing in self.crossings[s].iter() {
+				let letters: FnvHashSet<char> = domains[s].iter().map(|&w| self.words[w].str()[crossing.pos]).collect();
+				let t = crossing.other;
+				let before = domains[t].len();
+				let words = &self.words;
+				domains[t].retain(|&w| letters.contains(&words[w].str()[crossing.other_pos]));
+
+				if domains[t].is_empty() {
+					self.wipeouts[t] += 1;
+					return Err(t);
+				}
+				if domains[t].len() != before && !queued[t] {
+					queued[t] = true;
+					queue.push_back(t);
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// The slots that ran out of candidates most often.
+	fn failure(&self) -> FillFailure {
+		let most = self.wipeouts.iter().cloned().max().unwrap_or(0);
+		let mut hardest: Vec<usize> = (0..self.slots.len()).filter(|&s| self.wipeouts[s] > 0 && 2*self.wipeouts[s] >= most).collect();
+		hardest.sort_by_key(|&s| !0 - self.wipeouts[s]);
+		FillFailure { slots: hardest.into_iter().map(|s| self.slots[s].clone()).collect(), gave_up: self.steps > self.max_steps }
+	}
+}
+
+
+
+fn template(board: &Array<bool, MatrixDim>) -> Array<char, MatrixDim> {
+	board.map(|&open| if open { '_' } else { '#' })
+}
+
+
+
+//---- fill_with_blocks ----------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct BlockParams {
+	/// How many open cells may be blocked, on top of the board's own blocks.
+	pub max_blocks: usize,
+	pub symmetry: Symmetry,
+}
+
+const DESIGN_ATTEMPTS: usize = 100;
+const DESIGN_FILL_STEPS: usize = 20000;
+
+/// Fills the board like `full_fill`, but first lets the solver block up to `params.max_blocks` open cells, turning the board
+/// into a standard blocked crossword. Blocks go where a slot has no word of its length or keeps the fill from succeeding;
+/// open cells no slot covers are blocked as well. The blocks stay symmetric, and if the open cells of the board are connected,
+/// they stay connected. Every attempt starts over from the board, so the blocks differ between attempts. Returns the board
+/// with the chosen blocks, every open cell of which is covered by a placement, and the placements.
+pub fn fill_with_blocks(board: &Array<bool, MatrixDim>, dic: &[Word], params: &BlockParams, rng: &dyn AbstractRng)
+		-> Result<(Array<bool, MatrixDim>, Vec<Placement>), String> {
+	design_blocks(board, &Array::default(board.dim()), dic, params, rng)
+}
+
+
+/// Turns a layout, e.g. the constructor's, into a blocked crossword the way `fill_with_blocks` does: the letters of the placed
+/// words stay where they are, and every cell they leave empty is either blocked or filled by a word crossing or extending
+/// them. The result has one placement per slot of the blocked board, so a layout word comes back as it was only if its slot
+/// ends where it does; a word that gets extended is replaced by the longer word filling its slot.
+pub fn complete_layout(board: &Array<bool, MatrixDim>, dic: &[Word], placements: &[Placement], params: &BlockParams,
+					   rng: &dyn AbstractRng) -> Result<(Array<bool, MatrixDim>, Vec<Placement>), String> {
+	design_blocks(board, &letter_grid(board.dim(), placements), dic, params, rng)
+}
+
+
+fn design_blocks(board: &Array<bool, MatrixDim>, fixed: &Array<Option<char>, MatrixDim>, dic: &[Word], params: &BlockParams,
+				 rng: &dyn AbstractRng) -> Result<(Array<bool, MatrixDim>, Vec<Placement>), String> {
+	let lens: FnvHashSet<dim> = dic.iter().map(|word| word.len()).collect();
+	let connected = is_connected(&template(board));
+	let mut last = String::new();
+
+	for _ in 0..DESIGN_ATTEMPTS {
+		let mut design = BlockDesign { board: board.clone(), fixed, blocks: 0, params, connected };
+		let own_blocks: Vec<MatrixDim> = board.indexed_iter().filter(|&(_, &open)| !open).map(|(cell, _)| cell).collect();
+		for cell in own_blocks {
+			if !design.block(cell) {
+				return Err("the board's own blocks cannot be made symmetric without blocking a letter of the layout".to_string());
+			}
+		}
+		if design.blocks > params.max_blocks {
+			return Err(format!("making the board's own blocks symmetric takes {} more blocks", design.blocks));
+		}
+
+		loop {
+			let open_slots = slots(&template(&design.board));
+			let mut covered = Array::from_elem(design.board.dim(), false);
+			for slot in open_slots.iter() {
+				for cell in slot.cells() {
+					covered[cell] = true;
+				}
+			}
+			// blocked all at once: each of them is cut off from the rest, so the open cells only get connected again together
+			let uncovered: Vec<MatrixDim> = design.board.indexed_iter().filter(|&(cell, &open)| open && !covered[cell])
+				.map(|(cell, _)| cell).collect();
+			if !uncovered.is_empty() {
+				match design.with_blocks(&uncovered) {
+					Some(next) => {
+						design = next;
+						continue;
+					},
+					None => {
+						last = "blocking the cells no word covers took too many blocks or cut the open cells apart".to_string();
+						break;
+					}
+				}
+			}
+
+			let wordless: Vec<&Slot> = open_slots.iter().filter(|slot| !lens.contains(&slot.len)).collect();
+			let stuck = if !wordless.is_empty() {
+				wordless[rng.gen_usize(Range::new(0, wordless.len()))].clone()
+			} else {
+				match fill_slots(&design.board, fixed, dic, rng, DESIGN_FILL_STEPS) {
+					Ok(placements) => return Ok((design.board, placements)),
+					Err(failure) => {
+						last = failure.describe();
+						match failure.slots.into_iter().next() {
+							Some(slot) => slot,
+							None => break,
+						}
+					}
+				}
+			};
+
+			if !design.split(&stuck, &lens, rng) {
+				last = format!("no cell of the {:?} slot of {} letters at row {}, col {} could be blocked", stuck.orientation,
+							   stuck.len, stuck.y, stuck.x);
+				break;
+			}
+		}
+	}
+
+	Err(format!("no fill found with at most {} more blocks in {} attempts; the last one ended with: {}",
+				params.max_blocks, DESIGN_ATTEMPTS, last))
+}
+
+
+struct BlockDesign<'a> {
+	board: Array<bool, MatrixDim>,
+	fixed: &'a Array<Option<char>, MatrixDim>, // letters that are never blocked
+	blocks: usize, // how many open cells were blocked so far
+	params: &'a BlockParams,
+	connected: bool, // whether the open cells must stay connected
+}
+
+impl<'a> BlockDesign<'a> {
+	/// Blocks the cell and its mirror image, if they are still open. Fails, blocking neither, if one of them holds a fixed
+	/// letter.
+	fn block(&mut self, cell: MatrixDim) -> bool {
+		let MatrixDim(y, x) = cell;
+		let (my, mx) = self.params.symmetry.mirror(self.board.dim(), y, x);
+		let cells = [cell, MatrixDim(my, mx)];
+		if cells.iter().any(|&cell| self.fixed[cell].is_some()) {
+			return false;
+		}
+		for &cell in cells.iter() {
+			if self.board[cell] {
+				self.board[cell] = false;
+				self.blocks += 1;
+			}
+		}
+		true
+	}
+
+	/// The design with the cells and their mirror images blocked, unless one of them holds a fixed letter, or that goes over
+	/// the budget or leaves the open cells apart.
+	fn with_blocks(&self, cells: &[MatrixDim]) -> Option<BlockDesign<'a>> {
+		let mut next = BlockDesign { board: self.board.clone(), ..*self };
+		for &cell in cells {
+			if !next.block(cell) {
+				return None;
+			}
+		}
+		if next.blocks > self.params.max_blocks || (self.connected && !is_connected(&template(&next.board))) {
+			None
+		} else {
+			Some(next)
+		}
+	}
+
+	/// Blocks a random cell of the slot within the budget and without cutting the open cells apart, preferring one that
+	/// leaves pieces of the slot that words can fill. Fails if no cell qualifies.
+	fn split(&mut self, slot: &Slot, lens: &FnvHashSet<dim>, rng: &dyn AbstractRng) -> bool {
+		let mut cells = slot.cells();
+		for i in 0..cells.len() {
+			let j = rng.gen_usize(Range::new(i, cells.len()));
+			cells.swap(i, j);
+		}
+
+		let fits = |len: dim| len < 2 || lens.contains(&len);
+		let mut fallback = None;
+		for cell in cells {
+			let next = match self.with_blocks(&[cell]) {
+				Some(next) => next,
+				None => continue,
+			};
+
+			let MatrixDim(y, x) = cell;
+			let pos = (y - slot.y) + (x - slot.x);
+			if fits(pos) && fits(slot.len - pos - 1) {
+				*self = next;
+				return true;
+			}
+			fallback = fallback.or(Some(next));
+		}
+
+		match fallback {
+			Some(next) => {
+				*self = next;
+				true
+			},
+			None => false,
+		}
+	}
+}
+
+
+#[cfg(test)]
+mod full_fill_tests {
+	use super::*;
+	use common::{make_rng, test_word, Orientation};
+
+	fn dic(words: &[&str]) -> Vec<Word> {
+		words.iter().enumerate().map(|(i, word)| test_word(i, word)).collect()
+	}
+
+	#[test]
+	fn fills_double_word_square() {
+		let mut board = Array::from_elem(MatrixDim(3, 4), true);
+		for y in 0..3 {
+			board[MatrixDim(y, 3)] = false;
+		}
+		let dic = dic(&["cat", "bit", "ore", "wed", "dog", "bow", "ire", "ted", "tab"]);
+		let placements = full_fill(&board, &dic, &*make_rng()).unwrap();
+		assert_eq!(placements.len(), 6);
+
+		let mut words: Vec<String> = placements.iter().map(|place| place.word.to_string()).collect();
+		words.sort();
+		assert_eq!(words, vec!["bit", "bow", "ire", "ore", "ted", "wed"]);
+		let across = placements.iter().find(|place| place.orientation == Orientation::HOR && place.y == 1).unwrap();
+		assert_eq!(across.word.to_string(), "ore");
+	}
+
+	#[test]
+	fn reports_unsatisfiable_slots() {
+		let board = Array::from_elem(MatrixDim(2, 3), true);
+		let failure = full_fill(&board, &dic(&["abc", "def"]), &*make_rng()).unwrap_err();
+		assert_eq!(failure.slots.len(), 3);   // no 2 letter word fits any of the columns
+		assert!(failure.slots.iter().all(|slot| slot.orientation == Orientation::VER && slot.len == 2));
+
+		// every slot has candidates, but "bd" is missing
+		let board = Array::from_elem(MatrixDim(2, 2), true);
+		let failure = full_fill(&board, &dic(&["ab", "cd", "ac", "bc"]), &*make_rng()).unwrap_err();
+		assert!(!failure.gave_up && !failure.slots.is_empty());
+	}
+
+	#[test]
+	fn blocks_where_no_word_fits() {
+		// no 4 letter words: a column has to go
+		let board = Array::from_elem(MatrixDim(3, 4), true);
+		let dic = dic(&["bit", "ore", "wed", "bow", "ire", "ted"]);
+		let params = BlockParams { max_blocks: 3, symmetry: Symmetry::None };
+		let (blocked, placements) = fill_with_blocks(&board, &dic, &params, &*make_rng()).unwrap();
+		assert_eq!(placements.len(), 6);
+		assert_eq!(blocked.iter().filter(|&&open| !open).count(), 3);
+		assert!((0..3).all(|y| !blocked[MatrixDim(y, 0)]) || (0..3).all(|y| !blocked[MatrixDim(y, 3)]));
+
+		let params = BlockParams { max_blocks: 2, symmetry: Symmetry::None };
+		assert!(fill_with_blocks(&board, &dic, &params, &*make_rng()).is_err());
+	}
+
+	#[test]
+	fn symmetric_blocks() {
+		// four words only fill a ring; with rotational symmetry, the center is the only cell that takes one block
+		let board = Array::from_elem(MatrixDim(3, 3), true);
+		let params = BlockParams { max_blocks: 1, symmetry: Symmetry::Rotational };
+		let (blocked, placements) = fill_with_blocks(&board, &dic(&["abc", "cde", "gfe", "ahg"]), &params, &*make_rng()).unwrap();
+		assert_eq!(blocked.indexed_iter().filter(|&(_, &open)| !open).map(|(cell, _)| cell).collect::<Vec<_>>(), vec![MatrixDim(1, 1)]);
+		assert_eq!(placements.len(), 4);
+	}
+
+	#[test]
+	fn completes_layout_around_its_words() {
+		// "ore" is placed in the middle row; the solver blocks the last column and fills in the rest
+		let board = Array::from_elem(MatrixDim(3, 4), true);
+		let dic = dic(&["cat", "ore", "wed", "cow", "are", "ted", "bit"]);
+		let layout = vec![Placement::new(0, Orientation::HOR, 1, 0, dic[1].clone())];
+		let params = BlockParams { max_blocks: 3, symmetry: Symmetry::None };
+		let (blocked, placements) = complete_layout(&board, &dic, &layout, &params, &*make_rng()).unwrap();
+		assert!((0..3).all(|y| !blocked[MatrixDim(y, 3)]));
+		assert_eq!(placements.len(), 6);
+		assert!(placements.iter().any(|place| place.orientation == Orientation::HOR && place.y == 1 && place.word.to_string() == "ore"));
+
+		// the layout's letters are never blocked, so without 4 letter words the first column cannot go
+		let layout = vec![Placement::new(0, Orientation::VER, 0, 0, dic[3].clone())];
+		let params = BlockParams { max_blocks: 3, symmetry: Symmetry::Mirror };
+		assert!(complete_layout(&board, &dic, &layout, &params, &*make_rng()).is_err());
+	}
+
+	#[test]
+	fn blocks_cut_off_cells_together() {
+		// mirroring the two blocks cuts off two opposite corners; only blocking both keeps the open cells connected
+		let mut board = Array::from_elem(MatrixDim(3, 4), true);
+		board[MatrixDim(0, 1)] = false;
+		board[MatrixDim(1, 3)] = false;
+		let dic = dic(&["ab", "cd", "ef", "cf", "ad"]);
+		let params = BlockParams { max_blocks: 4, symmetry: Symmetry::Rotational };
+		let (blocked, placements) = fill_with_blocks(&board, &dic, &params, &*make_rng()).unwrap();
+		assert!(!blocked[MatrixDim(0, 0)] && !blocked[MatrixDim(2, 3)]);
+		assert!(is_connected(&template(&blocked)));
+		assert_eq!(placements.len(), 5);
+
+		let params = BlockParams { max_blocks: 3, symmetry: Symmetry::Rotational };
+		assert!(fill_with_blocks(&board, &dic, &params, &*make_rng()).is_err());
+	}
+}
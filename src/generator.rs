@@ -0,0 +1,334 @@
+use std::collections::VecDeque;
+use rand::distributions::Range;
+use ndarray::Array;
+use common::{dim, AbstractRng, MatrixDim, Orientation, Symmetry, Word};
+use full_fill::fill_slots;
+use render::letter_grid;
+
+
+//---- LetterDist ----------------------------------------------------------------------
+/// Letter frequencies used to fill the open cells of a problem template.
+#[derive(Clone, Debug)]
+pub struct LetterDist {
+	letters: Vec<char>,
+	cumulative: Vec<f32>, // running sum of the weights, one per letter
+}
+
+const ENGLISH: &[(char, f32)] = &[
+	('a', 8.167), ('b', 1.492), ('c', 2.782), ('d', 4.253), ('e', 12.702), ('f', 2.228), ('g', 2.015), ('h', 6.094),
+	('i', 6.966), ('j', 0.153), ('k', 0.772), ('l', 4.025), ('m', 2.406), ('n', 6.749), ('o', 7.507), ('p', 1.929),
+	('q', 0.095), ('r', 5.987), ('s', 6.327), ('t', 9.056), ('u', 2.758), ('v', 0.978), ('w', 2.360), ('x', 0.150),
+	('y', 1.974), ('z', 0.074),
+];
+
+impl LetterDist {
+	/// Builds a distribution from (letter, weight) pairs; letters of zero weight are dropped.
+	pub fn new(weights: &[(char, f32)]) -> Result<LetterDist, String> {
+		let mut letters = vec![];
+		let mut cumulative = vec![];
+		let mut total = 0.;
+		for &(letter, weight) in weights {
+			if weight.is_nan() || weight < 0. {
+				return Err(format!("negative weight for '{}'", letter));
+			}
+			if weight > 0. {
+				total += weight;
+				letters.push(letter);
+				cumulative.push(total);
+			}
+		}
+
+		if letters.is_empty() {
+			return Err("no letter has a positive weight".to_string());
+		}
+		Ok(LetterDist { letters, cumulative })
+	}
+
+	/// Every letter from a to z is equally likely.
+	pub fn uniform() -> LetterDist {
+		let weights: Vec<(char, f32)> = ENGLISH.iter().map(|&(letter, _)| (letter, 1.)).collect();
+		LetterDist::new(&weights).unwrap()
+	}
+
+	/// Letter frequencies of English text.
+	pub fn english() -> LetterDist {
+		LetterDist::new(ENGLISH).unwrap()
+	}
+
+	/// Parses a UTF-8 file with one `<letter> <weight>` pair per line; empty lines and lines starting with `#` are ignored.
+	pub fn parse(bytes: &[u8]) -> Result<LetterDist, String> {
+		let text = String::from_utf8_lossy(bytes);
+		let mut weights = vec![];
+		for (line_no, line) in text.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let fields: Vec<&str> = line.split_whitespace().collect();
+			let letter = match fields.first().map(|letter| letter.chars().collect::<Vec<_>>()) {
+				Some(ref chars) if fields.len() == 2 && chars.len() == 1 && chars[0].is_alphabetic() => chars[0],
+				_ => return Err(format!("line {}: expected \"<letter> <weight>\", got \"{}\"", line_no+1, line)),
+			};
+			let weight = fields[1].parse::<f32>().map_err(|_| format!("line {}: bad weight \"{}\"", line_no+1, fields[1]))?;
+			weights.push((letter, weight));
+		}
+
+		LetterDist::new(&weights)
+	}
+
+	pub fn sample(&self, rng: &dyn AbstractRng) -> char {
+		let total = *self.cumulative.last().unwrap();
+		let point = rng.gen_f32(Range::new(0., total));
+		let idx = self.cumulative.iter().position(|&sum| point < sum).unwrap_or(self.letters.len() - 1);
+		self.letters[idx]
+	}
+}
+
+
+
+//---- Template ------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct TemplateParams {
+	pub h: dim,
+	pub w: dim,
+	/// Chance of a cell being blocked (`#`).
+	pub block_density: f32,
+	/// Chance of an open cell being left without a letter (`*`).
+	pub star_fraction: f32,
+	pub symmetry: Symmetry,
+}
+
+const TEMPLATE_ATTEMPTS: usize = 1000;
+
+/// Generates a template grid of `_`, `#` and `*` cells whose blocks follow the requested symmetry and whose open cells are
+/// all connected. Gives up after a number of attempts, which only happens with very dense blocks.
+pub fn random_template(params: &TemplateParams, rng: &dyn AbstractRng) -> Result<Array<char, MatrixDim>, String> {
+	let (h, w) = (params.h, params.w);
+	if h == 0 || w == 0 {
+		return Err("the template must have at least one row and column".to_string());
+	}
+	let mirror = |y: dim, x: dim| params.symmetry.mirror(MatrixDim(h, w), y, x);
+
+	for _ in 0..TEMPLATE_ATTEMPTS {
+		let mut templ: Array<char, MatrixDim> = Array::from_elem(MatrixDim(h, w), '_');
+		for y in 0..h {
+			for x in 0..w {
+				let (my, mx) = mirror(y, x);
+				// every pair of symmetric cells is decided once, by the one that comes first
+				if (my, mx) < (y, x) {
+					templ[MatrixDim(y, x)] = templ[MatrixDim(my, mx)];
+				} else if rng.gen_f32(Range::new(0., 1.)) < params.block_density {
+					templ[MatrixDim(y, x)] = '#';
+				}
+			}
+		}
+
+		if !is_connected(&templ) {
+			continue;
+		}
+
+		for cell in templ.iter_mut() {
+			if *cell == '_' && rng.gen_f32(Range::new(0., 1.)) < params.star_fraction {
+				*cell = '*';
+			}
+		}
+		return Ok(templ);
+	}
+
+	Err(format!("no template with connected open cells found in {} attempts; try a lower block density", TEMPLATE_ATTEMPTS))
+}
+
+
+/// Whether the open (not `#`) cells form a single area, moving between cells that share a side; a grid without open cells
+/// is not connected.
+pub fn is_connected(templ: &Array<char, MatrixDim>) -> bool {
+	let MatrixDim(h, w) = templ.dim();
+	let open = templ.iter().filter(|&&c| c != '#').count();
+	let start = match templ.indexed_iter().find(|&(_, &c)| c != '#') {
+		Some((idx, _)) => idx,
+		None => return false,
+	};
+
+	let mut seen: Array<bool, MatrixDim> = Array::default(templ.dim());
+	let mut queue = VecDeque::new();
+	seen[start] = true;
+	queue.push_back(start);
+	let mut reached = 0;
+	while let Some(MatrixDim(y, x)) = queue.pop_front() {
+		reached += 1;
+		let neighbours = [(y.wrapping_sub(1), x), (y+1, x), (y, x.wrapping_sub(1)), (y, x+1)];
+		for &(ny, nx) in neighbours.iter() {
+			let idx = MatrixDim(ny, nx);
+			if ny < h && nx < w && templ[idx] != '#' && !seen[idx] {
+				seen[idx] = true;
+				queue.push_back(idx);
+			}
+		}
+	}
+
+	reached == open
+}
+
+
+
+//---- Slot ----------------------------------------------------------------------------
+/// A run of two or more letter cells in a template, i.e. the place of one dictionary word.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slot {
+	pub orientation: Orientation,
+	pub y: dim,
+	pub x: dim,
+	pub len: dim,
+}
+
+impl Slot {
+	pub fn cells(&self) -> Vec<MatrixDim> {
+		let (dy, dx) = self.orientation.align(0, 1);
+		(0..self.len).map(|i| MatrixDim(self.y + i*dy, self.x + i*dx)).collect()
+	}
+}
+
+
+/// Finds the slots of a template: maximal runs of cells other than `#` and `*`, first the vertical ones column by column,
+/// then the horizontal ones row by row.
+pub fn slots(templ: &Array<char, MatrixDim>) -> Vec<Slot> {
+	let MatrixDim(h, w) = templ.dim();
+	let mut slots = vec![];
+	for &orientation in Orientation::values().iter() {
+		let (lines, line_len) = match orientation {
+			Orientation::VER => (w, h),
+			Orientation::HOR => (h, w),
+		};
+		for i in 0..lines {
+			let mut run_start = 0;
+			for j in 0..line_len+1 {
+				let (y, x) = orientation.align(i, j);
+				if j == line_len || templ[MatrixDim(y, x)] == '#' || templ[MatrixDim(y, x)] == '*' {
+					if j - run_start > 1 {
+						let (y, x) = orientation.align(i, run_start);
+						slots.push(Slot { orientation, y, x, len: j - run_start });
+					}
+					run_start = j+1;
+				}
+			}
+		}
+	}
+	slots
+}
+
+
+const FILL_STEPS: usize = 100000;
+
+/// Fills every slot of a template with a distinct word of the list, so that crossing words agree on their shared letter.
+/// Open cells are `_`; cells that already hold a letter constrain the words crossing them. The slots are solved like the
+/// slots of `full_fill`, giving up after a fixed number of steps.
+pub fn fill_with_words(templ: &mut Array<char, MatrixDim>, words: &[String], rng: &dyn AbstractRng) -> Result<(), String> {
+	// stars end slots just like blocks, and the letters already in the template stay
+	let board = templ.map(|&c| c != '#' && c != '*');
+	let fixed = templ.map(|&c| if c == '#' || c == '*' || c == '_' { None } else { Some(c) });
+	let dic: Vec<Word> = words.iter().enumerate().map(|(i, word)| Word::new(i, &word.chars().collect::<Vec<char>>())).collect();
+
+	let placements = fill_slots(&board, &fixed, &dic, rng, FILL_STEPS).map_err(|failure| failure.describe())?;
+	for (cell, &letter) in letter_grid(templ.dim(), &placements).indexed_iter() {
+		if let Some(letter) = letter {
+			templ[cell] = letter;
+		}
+	}
+	Ok(())
+}
+
+
+
+#[cfg(test)]
+mod generator_tests {
+	use super::*;
+	use std::collections::HashSet;
+	use common::make_rng;
+
+	#[test]
+	fn letter_distributions() {
+		let rng = make_rng();
+		let dist = LetterDist::parse("# vowels only\na 3\n\nю 1\nz 0\n".as_bytes()).unwrap();
+		let samples: Vec<char> = (0..1000).map(|_| dist.sample(&*rng)).collect();
+		assert!(samples.iter().all(|&c| c == 'a' || c == 'ю'));
+		let a_count = samples.iter().filter(|&&c| c == 'a').count();
+		assert!(a_count > 650 && a_count < 850);
+
+		let english = LetterDist::english();
+		let e_count = (0..1000).filter(|_| english.sample(&*rng) == 'e').count();
+		assert!(e_count > 80 && e_count < 180);
+
+		assert!(LetterDist::parse(b"ab 1\n").is_err());
+		assert!(LetterDist::parse(b"a 0\n").is_err());
+	}
+
+	#[test]
+	fn symmetric_connected_templates() {
+		let rng = make_rng();
+		for &symmetry in [Symmetry::Rotational, Symmetry::Mirror, Symmetry::None].iter() {
+			let params = TemplateParams { h: 9, w: 11, block_density: 0.2, star_fraction: 0.1, symmetry };
+			let templ = random_template(&params, &*rng).unwrap();
+			assert_eq!(templ.dim(), MatrixDim(9, 11));
+			assert!(is_connected(&templ));
+
+			for ((y, x), &c) in templ.indexed_iter().map(|(MatrixDim(y, x), c)| ((y, x), c)) {
+				let mirrored = match symmetry {
+					Symmetry::Rotational => templ[MatrixDim(8-y, 10-x)],
+					Symmetry::Mirror => templ[MatrixDim(y, 10-x)],
+					Symmetry::None => c,
+				};
+				assert_eq!(c == '#', mirrored == '#');
+			}
+		}
+
+		let mut split = Array::from_elem(MatrixDim(3, 3), '_');
+		for x in 0..3 {
+			split[MatrixDim(1, x)] = '#';
+		}
+		assert!(!is_connected(&split));
+	}
+
+	fn template(rows: &[&str]) -> Array<char, MatrixDim> {
+		let mut templ = Array::from_elem(MatrixDim(rows.len(), rows[0].chars().count()), '_');
+		for (y, row) in rows.iter().enumerate() {
+			for (x, c) in row.chars().enumerate() {
+				templ[MatrixDim(y, x)] = c;
+			}
+		}
+		templ
+	}
+
+	#[test]
+	fn finds_slots() {
+		let found = slots(&template(&["__#", "_*_", "___"]));
+		let found: Vec<_> = found.iter().map(|slot| (slot.orientation, slot.y, slot.x, slot.len)).collect();
+		assert_eq!(found, vec![(Orientation::VER, 0, 0, 3), (Orientation::VER, 1, 2, 2), (Orientation::HOR, 0, 0, 2),
+							   (Orientation::HOR, 2, 0, 3)]);
+	}
+
+	#[test]
+	fn fills_crossing_words() {
+		let rng = make_rng();
+		let words: Vec<String> = ["cat", "car", "tar", "art", "rat", "act", "ace", "era", "tea", "ate", "arc", "eat"]
+			.iter().map(|w| w.to_string()).collect();
+		let mut templ = template(&["___", "_#_", "___"]);
+		fill_with_words(&mut templ, &words, &*rng).unwrap();
+
+		let mut placed = HashSet::new();
+		for slot in slots(&templ) {
+			let word: String = slot.cells().into_iter().map(|cell| templ[cell]).collect();
+			assert!(words.contains(&word), "{} is not in the list", word);
+			assert!(placed.insert(word));
+		}
+
+		// letters already in the template stay, and stars end the slots
+		let mut templ = template(&["e__", "_#*", "___"]);
+		fill_with_words(&mut templ, &words, &*rng).unwrap();
+		assert_eq!((templ[MatrixDim(0, 0)], templ[MatrixDim(1, 2)]), ('e', '*'));
+		assert!(words.contains(&slots(&templ)[0].cells().into_iter().map(|cell| templ[cell]).collect()));
+
+		let mut templ = template(&["__", "__"]);
+		assert!(fill_with_words(&mut templ, &words, &*rng).is_err());
+	}
+}
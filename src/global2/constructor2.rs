@@ -2,15 +2,20 @@ use std::cmp::Ordering;
 use std::rc::Rc;
 use std::f32;
 use std::cell::Cell;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 use rand::distributions::Range;
 
-use common::{dim, Placement, PlacementId, Word, make_rng, AbstractRng};
+use common::{dim, Placement, PlacementId, Word, MatrixDim, Symmetry, seeded_rng, AbstractRng};
+use rules::{LayoutRules, FreeForm};
 use fastmath::fastexp;
-use fixed_grid::{FixedGrid, Eff, AdjacencyInfo, PlaceMove};
+use fixed_grid::{FixedGrid, Eff, AdjacencyInfo, PlaceMove, objective, eff_t};
 use super::weighted_selection_tree::{WeightedSelectionTree, Item};
 use super::weighted_selection_tree;
 use super::data::ScoredMove;
 use super::variant_grid::{VariantGrid};
+use super::top_k::TopK;
+use solution::Solution;
 
 
 //const NRPA_LEVEL: u8 = 1;
@@ -37,6 +42,9 @@ const NRPA_ITERS: u32 = 100;
 const NRPA_ALPHA: f32 = 1.0;
 const MAX_STALLED_ITERS: u32 = 100;
 
+// how different, by the Jaccard distance of their placement sets, the layouts returned by `construct_top_k` are at least
+const TOP_K_MIN_DISTANCE: f32 = 0.3;
+
 
 
 //const NRPA_ALPHA: f32 = 0.03125;
@@ -113,16 +121,76 @@ type ResolutionMap = WeightedSelectionTree<PlacementId, AdjacencyResolver>;
 
 
 
+/// What happened in one restart of `Constructor::construct`.
+#[derive(Clone, Debug)]
+pub struct RestartStats {
+    pub seed: u32,
+    pub rollouts: usize,
+    /// How many rollouts ended with each objective value, once their layout was fixed up: `scores[n]` rollouts scored n.
+    pub scores: Vec<usize>,
+    /// The objective of the layout the restart returned.
+    pub best: eff_t,
+    pub elapsed: Duration,
+}
+
+impl RestartStats {
+    fn new(seed: u32) -> RestartStats {
+        RestartStats { seed, rollouts: 0, scores: vec![], best: 0, elapsed: Duration::from_secs(0) }
+    }
+
+    fn record(&mut self, eff: Eff) {
+        let score = *eff as usize;
+        if self.scores.len() <= score {
+            self.scores.resize(score + 1, 0);
+        }
+        self.scores[score] += 1;
+        self.rollouts += 1;
+    }
+
+    pub fn mean(&self) -> f64 {
+        let total: usize = self.scores.iter().enumerate().map(|(score, &count)| score * count).sum();
+        if self.rollouts == 0 { 0. } else { total as f64 / self.rollouts as f64 }
+    }
+
+    /// The lowest score at least `fraction` of the rollouts did not exceed, e.g. the median for 0.5.
+    pub fn quantile(&self, fraction: f64) -> usize {
+        let mut seen = 0;
+        for (score, &count) in self.scores.iter().enumerate() {
+            seen += count;
+            if seen > 0 && seen as f64 >= fraction * self.rollouts as f64 {
+                return score;
+            }
+        }
+        0
+    }
+}
+
+
+
 pub struct Constructor {
+    dic: Vec<Word>,
     places: Rc<Vec<Placement>>,
     placements_per_word: Vec<Vec<PlacementId>>,  // TODO: we might want to dynamically remove placements in the algorithm
     pub h: dim,
     pub w: dim,
-    rng: Box<AbstractRng>
+    rng: Box<dyn AbstractRng>,
+    rules: Rc<dyn LayoutRules>,
+    symmetry: Symmetry,
+    top: Option<TopK>, // the best distinct layouts of all rollouts, while `construct_top_k` runs
+    seed: u32,
+    restarts: usize,
+    budget: Option<Duration>,
+    deadline: Option<Instant>, // when the current restart has to stop
+    stats: Vec<RestartStats>
 }
 
 impl Constructor {
     pub fn new(h: dim, w: dim, dic: &[Word], places: &[Placement]) -> Constructor {
+        Self::with_seed(h, w, dic, places, 0)
+    }
+
+    /// Same as `new`, but the rollouts draw from a generator seeded with `seed` (0 is the default seed).
+    pub fn with_seed(h: dim, w: dim, dic: &[Word], places: &[Placement], seed: u32) -> Constructor {
         let places = places.iter().cloned().collect::<Vec<_>>();
 
         let mut placements_per_word = vec![vec![]; dic.len()];
@@ -130,16 +198,86 @@ impl Constructor {
             placements_per_word[place.word.id as usize].push(place.id);
         }
 
-        Constructor { placements_per_word:placements_per_word, places:Rc::new(places), h:h, w:w, rng:make_rng() }
+        Constructor { dic:dic.to_vec(), placements_per_word, places:Rc::new(places), h, w, rng:seeded_rng(seed), rules:Rc::new(FreeForm),
+                      symmetry:Symmetry::None, top:None, seed, restarts:1, budget:None, deadline:None, stats:vec![] }
+    }
+
+    /// Lays out words by `rules` instead of the default free-form rules. `places` should only hold placements the rules admit.
+    pub fn set_rules(&mut self, rules: Rc<dyn LayoutRules>) {
+        self.rules = rules;
+    }
+
+    /// Only builds layouts whose occupied cells look the same after the given symmetry, e.g. a half turn: every word needs
+    /// a word of the same length at the mirrored position (its own position, for a word in the middle). Rollouts choose
+    /// the mirror image of a word right after the word, and drop the placements that cannot have one.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.symmetry = symmetry;
+    }
+
+    /// Splits `construct` into `restarts` independent searches, each from a fresh policy and with its own seed (the
+    /// constructor's seed, plus one for every restart), and keeps the best layout of all. With a `budget`, the searches stop
+    /// when their share of it is used up; time a restart leaves over goes to the next one.
+    pub fn set_restarts(&mut self, restarts: usize, budget: Option<Duration>) {
+        self.restarts = restarts;
+        self.budget = budget;
+    }
+
+    /// One entry per restart of the last `construct` call.
+    pub fn restart_stats(&self) -> &[RestartStats] {
+        &self.stats
+    }
+
+    pub fn construct(&mut self) -> Solution {
+        let start = Instant::now();
+        self.stats = vec![];
+        let mut best: (eff_t, Vec<Placement>, u32) = (-1, vec![], self.seed);
+
+        for restart in 0..self.restarts {
+            let seed = self.seed.wrapping_add(restart as u32);
+            self.rng = seeded_rng(seed);
+            self.deadline = self.budget.map(|budget| restart_deadline(start, budget, restart, self.restarts));
+            self.stats.push(RestartStats::new(seed));
+
+            let restart_start = Instant::now();
+            let layout = self.construct_once();
+            let score = *objective(MatrixDim(self.h, self.w), &layout);
+            {
+                let stats = self.stats.last_mut().unwrap();
+                stats.best = score;
+                stats.elapsed = restart_start.elapsed();
+            }
+
+            if score > best.0 {
+                best = (score, layout, seed);
+            }
+        }
+
+        self.deadline = None;
+        let (_, layout, seed) = best;
+        self.solution(layout, start.elapsed(), seed)
     }
 
-    pub fn construct(&mut self) -> Vec<Placement> {
+    fn solution(&self, layout: Vec<Placement>, elapsed: Duration, seed: u32) -> Solution {
+        let rollouts = self.stats.iter().map(|restart| restart.rollouts).sum();
+        Solution::new(MatrixDim(self.h, self.w), &self.dic, layout, elapsed, rollouts, seed)
+    }
+
+    fn construct_once(&mut self) -> Vec<Placement> {
         let moves: Vec<_> = self.places.iter().map(|p| ScoredMove { place:p.clone(), score: 0., exp_score: 1. }).collect();
-        let mut variants = VariantGrid::new(self.places.clone(), self.h, self.w);
+        let mut variants = VariantGrid::new(self.places.clone(), self.h, self.w, &*self.rules, self.symmetry);
         let (_, best_valid_seq) = self.nrpa(NRPA_LEVEL, &mut variants, &moves);
         best_valid_seq.seq.into_iter().map(|mv| mv.0).collect()
     }
 
+    /// Runs `construct`, but returns up to `k` of the best layouts any rollout came up with, best first. Each layout differs
+    /// from the others by at least TOP_K_MIN_DISTANCE of Jaccard distance between their placement sets.
+    pub fn construct_top_k(&mut self, k: usize) -> Vec<Solution> {
+        self.top = Some(TopK::new(k, TOP_K_MIN_DISTANCE));
+        let elapsed = self.construct().elapsed;
+        let layouts = self.top.take().unwrap().into_layouts();
+        layouts.into_iter().map(|(layout, seed)| self.solution(layout, elapsed, seed)).collect()
+    }
+
     // http://www.chrisrosin.com/rosin-ijcai11.pdf
     fn nrpa(&mut self, level: u8, variants: &mut VariantGrid, parent_policy: &[ScoredMove]) -> (ChosenSequence, ChosenSequence) {
         if level == 0 {
@@ -156,6 +294,10 @@ impl Constructor {
             let mut last_progress = 0;
 
             for iter in 0..NRPA_ITERS {
+                if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    break;
+                }
+
                 let (new_seq, new_valid_seq) = self.nrpa(level - 1, variants, &policy);
                 self.debug1(level, &policy, &new_seq, &new_valid_seq); // TODO debug
 
@@ -245,7 +387,15 @@ impl Constructor {
         }
 
         // 2. remove incompatible placements from the variant grid
-        let incompat_ids = grid.remove_incompat(mv.place.id);
+        let mut incompat_ids = grid.remove_incompat(mv.place.id);
+
+        // 3. with a symmetric layout, also remove the placements that no longer have a mirror image
+        if grid.is_symmetric() {
+            let mut rmvd_ids: Vec<PlacementId> = rmvd_word_moves.iter().map(|mv| mv.key()).collect();
+            rmvd_ids.extend_from_slice(&incompat_ids);
+            let unmirrored = grid.remove_unmirrored(&rmvd_ids);
+            incompat_ids.extend(unmirrored);
+        }
 
         let mut resolvers_to_rm: Vec<PlacementId> = Vec::with_capacity(incompat_ids.len());
         let mut moves_to_rm: Vec<PlacementId> = Vec::with_capacity(incompat_ids.len());
@@ -293,7 +443,7 @@ impl Constructor {
 //            self.choose_resolver(select_tree, &resolution_map)
             self.select_proportional(resolution_map).mv
         };
-        grid.remove(mv.key());
+        grid.place(mv.key());
 
         // 2. filter out incompatible placements (we must do this before the subsequent steps, because they depend on it)
         let excl: Vec<ScoredMove> = self.remove_incompat(&mv, grid, select_tree, resolution_map);
@@ -438,6 +588,16 @@ impl Constructor {
 
         resolution_map.insert_bulk(entries);
 
+        // 4) with a symmetric layout, the word's mirror image must be chosen as well
+        let mirror_ids: Vec<PlacementId> = variant_grid.mirrors_of(chosen.place().id).into_iter()
+                                                       .filter(|&id| select_tree.contains_key(id))
+                                                       .collect();
+        let mirrors: Vec<ScoredMove> = select_tree.remove_bulk(&mirror_ids);
+        let entries = mirror_ids.into_iter().zip(mirrors).map(|(id, mv)|
+            (id, AdjacencyResolver { mv, adjs: vec![] })
+        ).collect::<Vec<_>>();
+        resolution_map.insert_bulk(entries);
+
         return success
     }
 
@@ -463,12 +623,14 @@ impl Constructor {
     #[inline(never)]
     fn nrpa_monte_carlo(&mut self, policy: &[ScoredMove], variants: &mut VariantGrid) -> (ChosenSequence, ChosenSequence) {
         let rng = self.rng.clone_to_box();
-        let mut fixed_grid = FixedGrid::new(self.h, self.w, &*rng);
+        let rules = self.rules.clone();
+        let mut fixed_grid = FixedGrid::new(self.h, self.w, &*rng, &*rules);
         let mut variants = variants.clone();
         let words_count = self.placements_per_word.len();
         let mut best_seq = ChosenSequence::new(Vec::with_capacity(words_count), Vec::with_capacity(words_count), Eff(0));
         {
             let mut select_tree: SelectTree = SelectTree::new(policy, policy.len());
+            select_tree.remove_bulk(variants.pruned());
 
             let mut resolution_map: ResolutionMap = WeightedSelectionTree::new(&[], policy.len());
 
@@ -495,7 +657,39 @@ impl Constructor {
 
         best_seq.eff = fixed_grid.efficiency();
 
-        let (valid, removed, valid_eff) = fixed_grid.fixup_adjacent();
+        let (mut valid, mut removed, mut valid_eff) = fixed_grid.fixup_adjacent();
+
+        // a word whose mirror image did not make it is dropped, which can break other pairs or leave letters uncrossed
+        while variants.is_symmetric() {
+            let on_grid: HashSet<PlacementId> = valid.iter().map(|mv| mv.0.id).collect();
+            let (paired, mut unpaired): (Vec<ChosenMove>, Vec<ChosenMove>) =
+                valid.into_iter().partition(|mv| variants.has_mirror_in(mv.0.id, |id| on_grid.contains(&id)));
+            if unpaired.is_empty() {
+                valid = paired;
+                break;
+            }
+
+            removed.append(&mut unpaired);
+            let mut grid = FixedGrid::new(self.h, self.w, &*rng, &*rules);
+            grid.place_all(paired);
+            let (fixed, mut fixed_removed, fixed_eff) = grid.fixup_adjacent();
+            valid = fixed;
+            removed.append(&mut fixed_removed);
+            valid_eff = fixed_eff;
+        }
+
+        let seed = match self.stats.last_mut() {
+            Some(stats) => {
+                stats.record(valid_eff);
+                stats.seed
+            },
+            None => self.seed
+        };
+        if let Some(ref mut top) = self.top {
+            if top.admits(valid_eff) {
+                top.offer(valid_eff, valid.iter().map(|mv| mv.0.clone()).collect(), seed);
+            }
+        }
 
         let best_valid_seq = ChosenSequence::new(valid, removed, valid_eff);
         (best_seq, best_valid_seq)
@@ -504,9 +698,9 @@ impl Constructor {
 
 
     fn exp_score(mv: &ScoredMove) -> f32 {
-//        let s = fastexp(mv.score + (mv.place.word.str.len() as f32));
+//        let s = fastexp(mv.score + (mv.place.word.len() as f32));
 //        if s <= 0.0 {
-//            let s2 = (mv.score + (mv.place.word.str.len() as f32)).exp();
+//            let s2 = (mv.score + (mv.place.word.len() as f32)).exp();
 //            if s2.is_infinite() {
 //                f32::MAX
 //            } else {
@@ -517,8 +711,8 @@ impl Constructor {
 //        }
 
 
-//        let s = (mv.score + (mv.place.word.str.len() as f32)).exp();
-        let s = fastexp(mv.score + (mv.place.word.str.len() as f32));
+//        let s = (mv.score + (mv.place.word.len() as f32)).exp();
+        let s = fastexp(mv.score + (mv.place.word.len() as f32));
 
 
         if s.is_infinite() || s > 1.0e8 || s < 0.0 || s.is_nan() {
@@ -526,9 +720,9 @@ impl Constructor {
             1.0e8
         } else {
             if s.is_nan() {
-                println!("S IS NAN!!!! score={}, full={}, max={}", mv.score, mv.score + (mv.place.word.str.len() as f32), f32::MAX);
+                println!("S IS NAN!!!! score={}, full={}, max={}", mv.score, mv.score + (mv.place.word.len() as f32), f32::MAX);
 //            } else if s <= 0.000000000000001 {
-//                println!("S <= 0.000000001!!!! score={}, full={}, max={}", mv.score, mv.score + (mv.place.word.str.len() as f32), f32::MAX);
+//                println!("S <= 0.000000001!!!! score={}, full={}, max={}", mv.score, mv.score + (mv.place.word.len() as f32), f32::MAX);
             }
             s
         }
@@ -608,7 +802,7 @@ impl Constructor {
             let ranks : Vec<_> = new_seq.seq.iter().map(|cmv: &ChosenMove| (cmv.0.word.id, cmv.0.id.0, moves[cmv.0.id].score)).collect();
             println!("new ranks: {:?}", ranks);
 
-            let mut grid : FixedGrid<ChosenMove> = FixedGrid::new(self.h, self.w, &mut *self.rng);
+            let mut grid : FixedGrid<ChosenMove> = FixedGrid::new(self.h, self.w, &*self.rng, &*self.rules);
             grid.place_all(new_seq.seq.iter().cloned().collect());
             grid.print();
             println!("-------------- eff new: {} valid: {}, ----------------", *new_seq.eff, *new_valid_seq.eff);
@@ -675,3 +869,60 @@ impl Default for ChosenSequence {
     }
 }
 
+
+
+
+/// When restart `restart` of `restarts` has to stop: by the end of its share of the budget, counting the shares of the
+/// restarts before it, so time one of them leaves over goes to the next.
+fn restart_deadline(start: Instant, budget: Duration, restart: usize, restarts: usize) -> Instant {
+    start + budget * (restart as u32 + 1) / restarts as u32
+}
+
+
+#[cfg(test)]
+mod constructor_tests {
+    use super::*;
+    use common::{Problem, Orientation};
+    use ndarray::Array;
+
+    #[test]
+    fn score_distribution() {
+        let mut stats = RestartStats::new(0);
+        for &score in [2, 3, 3, 5].iter() {
+            stats.record(Eff(score));
+        }
+        assert_eq!(stats.scores, vec![0, 0, 1, 2, 0, 1]);
+        assert_eq!((stats.rollouts, stats.mean()), (4, 3.25));
+        assert_eq!((stats.quantile(0.), stats.quantile(0.5), stats.quantile(1.)), (2, 3, 5));
+    }
+
+    #[test]
+    fn restarts_share_the_budget() {
+        let problem = Problem::new(vec!["ab".to_string(), "ac".to_string(), "bc".to_string()], Array::from_elem(MatrixDim(2, 2), true));
+        let places: Vec<Placement> = [(Orientation::HOR, 0, "ab"), (Orientation::VER, 0, "ac"), (Orientation::HOR, 1, "bc")].iter()
+            .enumerate()
+            .map(|(i, &(or, line, word))| {
+                let (y, x) = or.align(line, 0);
+                Placement::new(i, or, y, x, problem.dic.iter().find(|w| w.to_string() == word).unwrap().clone())
+            })
+            .collect();
+
+        let mut constructor = Constructor::with_seed(2, 2, &problem.dic, &places, 7);
+        constructor.set_restarts(3, Some(Duration::from_millis(300)));
+        let solution = constructor.construct();
+
+        let stats = constructor.restart_stats();
+        assert_eq!(stats.iter().map(|restart| restart.seed).collect::<Vec<_>>(), vec![7, 8, 9]);
+        assert!(stats.iter().all(|restart| restart.rollouts > 0));
+        assert_eq!(*solution.objective, stats.iter().map(|restart| restart.best).max().unwrap());
+        assert_eq!(solution.rollouts, stats.iter().map(|restart| restart.rollouts).sum::<usize>());
+        assert!(stats.iter().any(|restart| restart.seed == solution.seed && restart.best == *solution.objective));
+
+        // restart i has to be done by the end of the first i+1 thirds of the budget; the wall clock only catches a budget
+        // that is ignored (the search runs for seconds without one), as loaded machines overrun the deadlines
+        let start = Instant::now();
+        let deadlines: Vec<Duration> = (0..3).map(|i| restart_deadline(start, Duration::from_millis(300), i, 3) - start).collect();
+        assert_eq!(deadlines, vec![Duration::from_millis(100), Duration::from_millis(200), Duration::from_millis(300)]);
+        assert!(solution.elapsed < Duration::from_secs(3), "took {:?}", solution.elapsed);
+    }
+}
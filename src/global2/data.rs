@@ -2,7 +2,9 @@ use common::{Placement, PlacementId};
 use super::weighted_selection_tree;
 use fixed_grid::PlaceMove;
 
+// the nightly remove_bulk__copy transmutes moves to 16-byte aligned items, so their sizes must match
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "nightly", repr(align(16)))]
 pub struct ScoredMove {
     pub place: Placement,
 	pub exp_score: f32,
@@ -2,6 +2,7 @@ pub mod data;
 pub mod weighted_selection_tree;
 pub mod constructor2;
 pub mod sliced_arena;
+pub mod top_k;
 pub use self::variant_grid::*;
 
 mod variant_grid;
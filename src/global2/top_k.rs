@@ -0,0 +1,122 @@
+use common::{Placement, PlacementId};
+use fixed_grid::Eff;
+
+
+/// The best layouts seen so far, best first, that differ pairwise by at least a minimum Jaccard distance of their placement
+/// sets. A layout that is too close to a better or equally good one is dropped; one that is too close to worse ones replaces
+/// them.
+pub struct TopK {
+    k: usize,
+    min_distance: f32,
+    entries: Vec<Entry>
+}
+
+struct Entry {
+    eff: Eff,
+    ids: Vec<PlacementId>, // sorted
+    placements: Vec<Placement>,
+    seed: u32 // of the search that found the layout
+}
+
+
+impl TopK {
+    pub fn new(k: usize, min_distance: f32) -> TopK {
+        TopK { k, min_distance, entries:vec![] }
+    }
+
+    /// Whether a layout of the given objective could be kept; it is cheap, so callers can skip building layouts that could not.
+    #[inline]
+    pub fn admits(&self, eff: Eff) -> bool {
+        self.k > 0 && (self.entries.len() < self.k || *eff > *self.entries[self.entries.len() - 1].eff)
+    }
+
+    pub fn offer(&mut self, eff: Eff, placements: Vec<Placement>, seed: u32) {
+        if placements.is_empty() || !self.admits(eff) {
+            return;
+        }
+
+        let mut ids: Vec<PlacementId> = placements.iter().map(|place| place.id).collect();
+        ids.sort();
+
+        let similar: Vec<usize> = (0..self.entries.len())
+                                  .filter(|&i| jaccard_distance(&self.entries[i].ids, &ids) < self.min_distance)
+                                  .collect();
+        if similar.iter().any(|&i| *self.entries[i].eff >= *eff) {
+            return;
+        }
+        for &i in similar.iter().rev() {
+            self.entries.remove(i);
+        }
+
+        // after the layouts that are as good, so that the first one found wins a tie
+        let pos = self.entries.iter().position(|entry| *entry.eff < *eff).unwrap_or(self.entries.len());
+        self.entries.insert(pos, Entry { eff, ids, placements, seed });
+        self.entries.truncate(self.k);
+    }
+
+    /// The layouts, best first, with the seeds they were offered with.
+    pub fn into_layouts(self) -> Vec<(Vec<Placement>, u32)> {
+        self.entries.into_iter().map(|entry| (entry.placements, entry.seed)).collect()
+    }
+}
+
+
+/// 1 - |a ∩ b| / |a ∪ b| of two sorted id lists; 0 for two empty lists.
+pub fn jaccard_distance(a: &[PlacementId], b: &[PlacementId]) -> f32 {
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            common += 1;
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    let union = a.len() + b.len() - common;
+    if union == 0 {
+        0.
+    } else {
+        1. - common as f32 / union as f32
+    }
+}
+
+
+
+#[cfg(test)]
+mod top_k_tests {
+    use super::*;
+    use common::{test_word, Orientation};
+
+    fn layout(ids: &[usize]) -> Vec<Placement> {
+        ids.iter().map(|&id| Placement::new(id, Orientation::HOR, id, 0, test_word(id, "ab"))).collect()
+    }
+
+    fn ids(top: TopK) -> Vec<Vec<usize>> {
+        top.into_layouts().iter().map(|(layout, _)| layout.iter().map(|place| place.id.0).collect()).collect()
+    }
+
+    #[test]
+    fn distances() {
+        let ids = |ids: &[usize]| ids.iter().map(|&id| PlacementId(id)).collect::<Vec<_>>();
+        assert_eq!(jaccard_distance(&ids(&[1, 2, 3]), &ids(&[1, 2, 3])), 0.);
+        assert_eq!(jaccard_distance(&ids(&[1, 2, 3]), &ids(&[2, 3, 4])), 0.5);
+        assert_eq!(jaccard_distance(&ids(&[1]), &ids(&[2])), 1.);
+        assert_eq!(jaccard_distance(&[], &[]), 0.);
+    }
+
+    #[test]
+    fn keeps_best_distinct_layouts() {
+        let mut top = TopK::new(2, 0.5);
+        top.offer(Eff(3), layout(&[1, 2, 3, 4]), 0);
+        top.offer(Eff(3), layout(&[1, 2, 3, 5]), 0);  // too close to an equally good one
+        top.offer(Eff(2), layout(&[6, 7]), 0);
+        top.offer(Eff(1), layout(&[8, 9]), 0);        // worse than both
+        assert!(!top.admits(Eff(2)));
+        top.offer(Eff(4), layout(&[6, 7, 8]), 0);     // replaces the close, worse one
+        assert_eq!(ids(top), vec![vec![6, 7, 8], vec![1, 2, 3, 4]]);
+    }
+}
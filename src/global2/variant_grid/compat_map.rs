@@ -1,4 +1,5 @@
 use common::{Placement, PlacementId};
+use rules::LayoutRules;
 use bit_set::BitSet;
 
 use std::ops::Index;
@@ -10,14 +11,14 @@ pub struct CompatMap {
 
 
 impl CompatMap {
-    pub fn new(places: &[Placement]) -> CompatMap {
+    pub fn new(places: &[Placement], rules: &dyn LayoutRules) -> CompatMap {
         let mut sets = Vec::new();
         for place in places.iter() {
             let pid = place.id;
             let mut set: BitSet<usize> = Default::default();
 
             for place2 in places.iter() {
-                if !CompatMap::check_compat(place, place2) {
+                if !rules.compatible(place, place2) {
                     set.insert(place2.id.0);
                 }
             }
@@ -31,11 +32,6 @@ impl CompatMap {
     pub fn compat(&self, id: PlacementId, other: PlacementId) -> bool {
         !self.sets[id].contains(other.0)
     }
-
-
-    fn check_compat(place: &Placement, place2: &Placement) -> bool {
-        place.compatible(place2)
-    }
 }
 
 
@@ -0,0 +1,34 @@
+use common::{dim, Placement, PlacementId, MatrixDim, Orientation, Symmetry};
+
+use std::cmp::min;
+use std::collections::HashMap;
+
+/// For every placement, the placements of the same length and orientation whose cells are the mirror image of its cells.
+pub struct MirrorMap {
+    mirrors: Vec<Vec<PlacementId>>
+}
+
+
+impl MirrorMap {
+    pub fn new(places: &[Placement], h: dim, w: dim, symmetry: Symmetry) -> MirrorMap {
+        let mut at: HashMap<(Orientation, dim, dim, dim), Vec<PlacementId>> = HashMap::new();
+        for place in places.iter() {
+            at.entry((place.orientation, place.y, place.x, place.word.len())).or_insert(vec![]).push(place.id);
+        }
+
+        let mirrors = places.iter().map(|place| {
+            let len = place.word.len();
+            let (dy, dx) = place.orientation.align(0, 1);
+            let (y0, x0) = symmetry.mirror(MatrixDim(h, w), place.y, place.x);
+            let (y1, x1) = symmetry.mirror(MatrixDim(h, w), place.y + (len-1)*dy, place.x + (len-1)*dx);
+            at.get(&(place.orientation, min(y0, y1), min(x0, x1), len)).cloned().unwrap_or(vec![])
+        }).collect();
+
+        MirrorMap { mirrors }
+    }
+
+    #[inline]
+    pub fn of(&self, id: PlacementId) -> &[PlacementId] {
+        &self.mirrors[id.0]
+    }
+}
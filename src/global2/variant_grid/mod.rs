@@ -1,10 +1,13 @@
 mod shrink_vec;
 mod compat_map;
+mod mirror_map;
 
 use self::shrink_vec::ShrinkVec;
 
-use common::{dim, Placement, PlacementId, Cond};
+use common::{dim, Placement, PlacementId, Cond, Symmetry};
+use rules::LayoutRules;
 use self::compat_map::CompatMap;
+use self::mirror_map::MirrorMap;
 
 use ndarray::{Array, Ix};
 use std::rc::Rc;
@@ -15,8 +18,6 @@ use super::sliced_arena::SlicedArena;
 
 const REMOVED: usize = !0;
 
-const ALWAYS_REMOVE: usize = ::std::usize::MAX;
-
 
 
 
@@ -39,7 +40,12 @@ pub struct VariantGrid {
     tmp_removed: Vec<PlacementId>,
 
 
-    compats: Rc<CompatMap>
+    compats: Rc<CompatMap>,
+    
+    // with a symmetric layout: the mirror images of every placement, the placements that have none and which were chosen
+    mirrors: Option<Rc<MirrorMap>>,
+    pruned: Rc<Vec<PlacementId>>,
+    placed: Vec<bool>
 }
 
 impl Clone for VariantGrid {
@@ -48,12 +54,15 @@ impl Clone for VariantGrid {
         let entries = self.entries.clone();
         let places = self.places.clone();
         let compats = self.compats.clone();
+        let mirrors = self.mirrors.clone();
+        let pruned = self.pruned.clone();
         
         // make sure the pointers inside ShrinkVecs are to the cloned cell_slices arena
         let (h, w) = self.field.dim();
         let field = Self::build_grid(h, w, &mut slices);
         
-        VariantGrid { cell_slices:slices, entries:entries, field:field, places:places, tmp_removed:vec![], compats:compats }
+        VariantGrid { cell_slices:slices, entries, field, places, tmp_removed:vec![], compats,
+                      mirrors, pruned, placed:self.placed.clone() }
     }
 }
 
@@ -78,7 +87,7 @@ impl VariantGrid {
     }
     
     #[inline(never)]
-    pub fn new(places: Rc<Vec<Placement>>, h: dim, w: dim) -> VariantGrid {
+    pub fn new(places: Rc<Vec<Placement>>, h: dim, w: dim, rules: &dyn LayoutRules, symmetry: Symmetry) -> VariantGrid {
         // 1. build the entries arena
         let place_word_lens = places.iter().map(|place| place.word.len()).collect::<Vec<_>>();
         let mut entries: SlicedArena<usize> = SlicedArena::new(&place_word_lens);
@@ -112,8 +121,26 @@ impl VariantGrid {
             });
         }
 
-        let compats = CompatMap::new(&places);
-        VariantGrid { field: field, cell_slices:cell_slices, entries: entries, places: places, tmp_removed: vec![], compats: Rc::new(compats) }
+        let compats = CompatMap::new(&places, rules);
+        let mirrors = match symmetry {
+            Symmetry::None => None,
+            _ => Some(Rc::new(MirrorMap::new(&places, h, w, symmetry)))
+        };
+        let placed = vec![false; places.len()];
+        let mut grid = VariantGrid { field, cell_slices, entries, places, tmp_removed: vec![],
+                                     compats: Rc::new(compats), mirrors, pruned: Rc::new(vec![]), placed };
+        
+        // 6. a placement without a mirror image can never be part of a symmetric layout
+        let pruned: Vec<PlacementId> = match grid.mirrors {
+            Some(ref mirrors) => grid.places.iter().map(|place| place.id).filter(|&id| mirrors.of(id).is_empty()).collect(),
+            None => vec![]
+        };
+        for &id in pruned.iter() {
+            grid.remove(id);
+        }
+        grid.pruned = Rc::new(pruned);
+        
+        grid
     }
     
     
@@ -171,18 +198,79 @@ impl VariantGrid {
             
         // remove incompatible placements in the cell to the left of the placement (for horizontal orientation)
         if u0 > 0 {
-            self.filter_incompat(place_y-yc, place_x-xc, &mut removed, PlacementId(ALWAYS_REMOVE));
+            self.filter_incompat(place_y-yc, place_x-xc, &mut removed, place_id);
         }
         
         // remove incompatible placements in the cell to the right of the placement (for horizontal orientation)
         if u0+len0 < maxu {
-            self.filter_incompat(place_y+len0.cond(yc), place_x+len0.cond(xc), &mut removed, PlacementId(ALWAYS_REMOVE));
+            self.filter_incompat(place_y+len0.cond(yc), place_x+len0.cond(xc), &mut removed, place_id);
         }
         
         removed
     }
     
     
+    /// Removes a placement that was chosen for the layout; with a symmetric layout, it still counts as a mirror image.
+    pub fn place(&mut self, place_id: PlacementId) {
+        self.remove(place_id);
+        if self.mirrors.is_some() {
+            self.placed[place_id.0] = true;
+        }
+    }
+    
+    
+    pub fn is_symmetric(&self) -> bool {
+        self.mirrors.is_some()
+    }
+    
+    
+    /// The placements that were removed up front because they have no mirror image.
+    pub fn pruned(&self) -> &[PlacementId] {
+        &self.pruned
+    }
+    
+    
+    /// The remaining placements that would be a mirror image of the given one.
+    pub fn mirrors_of(&self, place_id: PlacementId) -> Vec<PlacementId> {
+        match self.mirrors {
+            Some(ref mirrors) => mirrors.of(place_id).iter().cloned().filter(|&id| self.contains(id)).collect(),
+            None => vec![]
+        }
+    }
+    
+    
+    /// Whether the given placement, if it were on the grid, would have its mirror image on the grid too.
+    pub fn has_mirror_in<F: Fn(PlacementId) -> bool>(&self, place_id: PlacementId, on_grid: F) -> bool {
+        match self.mirrors {
+            Some(ref mirrors) => mirrors.of(place_id).iter().any(|&id| on_grid(id)),
+            None => true
+        }
+    }
+    
+    
+    /// With a symmetric layout, removes the placements that lost their last mirror image when `removed` were removed, and
+    /// so on. Returns the placements it removed.
+    pub fn remove_unmirrored(&mut self, removed: &[PlacementId]) -> Vec<PlacementId> {
+        let mirrors = match self.mirrors {
+            Some(ref mirrors) => mirrors.clone(),
+            None => return vec![]
+        };
+        
+        let mut unmirrored = vec![];
+        let mut queue = removed.to_vec();
+        while let Some(id) = queue.pop() {
+            for &other in mirrors.of(id) {
+                if self.contains(other) && !mirrors.of(other).iter().any(|&m| self.contains(m) || self.placed[m.0]) {
+                    self.remove(other);
+                    unmirrored.push(other);
+                    queue.push(other);
+                }
+            }
+        }
+        unmirrored
+    }
+    
+    
     // this is quite dirty
     pub fn contains(&self, place_id: PlacementId) -> bool {
         self.entries[place_id][0] != REMOVED
@@ -206,7 +294,7 @@ impl VariantGrid {
         tmp_removed.reserve_exact(self.field[(celly, cellx)].len());
         
         for &entry_id in self.field[(celly, cellx)].iter() {
-            if place_id.0 == ALWAYS_REMOVE || !self.compats.compat(place_id, entry_id) {
+            if !self.compats.compat(place_id, entry_id) {
                 tmp_removed.push(entry_id);
                 removed.push(entry_id);
             }
@@ -276,3 +364,47 @@ impl<T: Sized+Clone> IndexMut<PlacementId> for SlicedArena<T> {
 		self.index_mut(index.0 as usize)
     }
 }
+
+
+
+#[cfg(test)]
+mod variant_grid_tests {
+    use super::*;
+    use common::{test_word, Orientation};
+    use rules::FreeForm;
+
+    fn hor_grid(words: &[(dim, &str)], symmetry: Symmetry) -> VariantGrid {
+        let places = words.iter().enumerate()
+                          .map(|(i, &(x, word))| Placement::new(i, Orientation::HOR, 0, x, test_word(i, word)))
+                          .collect();
+        VariantGrid::new(Rc::new(places), 1, 6, &FreeForm, symmetry)
+    }
+
+    #[test]
+    fn placements_need_mirror_images() {
+        let mut grid = hor_grid(&[(0, "ab"), (4, "cd"), (1, "ef"), (4, "gh"), (2, "ij")], Symmetry::Rotational);
+        assert_eq!(grid.pruned(), &[PlacementId(2)]);
+        assert_eq!(grid.mirrors_of(PlacementId(0)), vec![PlacementId(1), PlacementId(3)]);
+        assert_eq!(grid.mirrors_of(PlacementId(4)), vec![PlacementId(4)]);   // its own mirror image
+
+        grid.remove(PlacementId(1));
+        assert!(grid.remove_unmirrored(&[PlacementId(1)]).is_empty());
+        grid.remove(PlacementId(3));
+        assert_eq!(grid.remove_unmirrored(&[PlacementId(3)]), vec![PlacementId(0)]);
+        assert!(grid.contains(PlacementId(4)));
+    }
+
+    #[test]
+    fn chosen_placements_count_as_mirror_images() {
+        let mut grid = hor_grid(&[(0, "ab"), (4, "cd"), (4, "gh")], Symmetry::Rotational);
+        grid.place(PlacementId(0));
+        grid.remove(PlacementId(1));
+        assert!(grid.remove_unmirrored(&[PlacementId(1)]).is_empty());
+        assert!(grid.contains(PlacementId(2)));
+
+        let mut grid = hor_grid(&[(0, "ab"), (2, "cd")], Symmetry::None);
+        assert!(grid.pruned().is_empty() && !grid.is_symmetric());
+        grid.remove(PlacementId(0));
+        assert!(grid.remove_unmirrored(&[PlacementId(0)]).is_empty());
+    }
+}
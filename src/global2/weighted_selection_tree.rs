@@ -601,48 +601,63 @@ trait WeightedSelectionTreeCopy<K: Key, It: Item<K>> {
 }
 
 
+// the aligned override below needs specialization, so the generic version is only a default on nightly
+#[cfg(feature = "nightly")]
 impl<K: Key, It: Item<K>> WeightedSelectionTreeCopy<K, It> for WeightedSelectionTree<K, It> {
-//    #[inline(never)]
     default fn remove_bulk__copy(&mut self, upd_set:&mut Vec<NdIndex>, rm_len:usize, displaced_assoc: Vec<usize>, rm_indices: Vec<usize>) -> Vec<It> {
-        let mut removed = Vec::with_capacity(rm_len);
-        
-        let new_len = self.data.len() - rm_len; 
-        let data_ptr = self.data.as_mut_ptr(); // we need the pointers to overcome the borrow checker that otherwise complains about two mutable references to data
-        let removed_ptr = removed.as_mut_ptr();
+        remove_bulk_copy_generic(self, upd_set, rm_len, displaced_assoc, rm_indices)
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<K: Key, It: Item<K>> WeightedSelectionTreeCopy<K, It> for WeightedSelectionTree<K, It> {
+    fn remove_bulk__copy(&mut self, upd_set:&mut Vec<NdIndex>, rm_len:usize, displaced_assoc: Vec<usize>, rm_indices: Vec<usize>) -> Vec<It> {
+        remove_bulk_copy_generic(self, upd_set, rm_len, displaced_assoc, rm_indices)
+    }
+}
+
+#[inline]
+fn remove_bulk_copy_generic<K: Key, It: Item<K>>(tree: &mut WeightedSelectionTree<K, It>, upd_set:&mut Vec<NdIndex>, rm_len:usize, displaced_assoc: Vec<usize>, rm_indices: Vec<usize>) -> Vec<It> {
+    let mut removed: Vec<It> = Vec::with_capacity(rm_len);
+    
+    let new_len = tree.data.len() - rm_len; 
+    let data_ptr = tree.data.as_mut_ptr(); // we need the pointers to overcome the borrow checker that otherwise complains about two mutable references to data
+    let removed_ptr = removed.as_mut_ptr();
+    
+    for (i, &iremoved) in displaced_assoc.iter().enumerate().take(rm_len) {
+        let rm_ptr = unsafe { removed_ptr.add(iremoved) };
+        let src_idx = rm_indices[iremoved];
         
-        for i in 0..rm_len {
-            let iremoved = displaced_assoc[i];
-            let rm_ptr = unsafe { removed_ptr.offset(iremoved as isize) };
-            let src_idx = rm_indices[iremoved];
+        unsafe {
+            let src_ptr = &mut (*data_ptr.add(src_idx)).item;
+            ptr::copy_nonoverlapping(src_ptr, rm_ptr, 1);
             
-            unsafe {
-                let src_ptr = &mut (*data_ptr.offset(src_idx as isize)).item;
-                ptr::copy_nonoverlapping(src_ptr, rm_ptr, 1);
-                
-                if src_idx < new_len {
-                    let displaced = &(*data_ptr.offset((new_len+i) as isize)).item;
-                    self.keys[displaced.key().usize()] = Some(src_idx);
-                    ptr::copy_nonoverlapping(displaced, src_ptr, 1);
-                    upd_set.push(src_idx);
-                }
+            if src_idx < new_len {
+                let displaced = &(*data_ptr.add(new_len+i)).item;
+                tree.keys[displaced.key().usize()] = Some(src_idx);
+                ptr::copy_nonoverlapping(displaced, src_ptr, 1);
+                upd_set.push(src_idx);
             }
         }
-        
-        unsafe {
-            self.data.set_len(new_len);
-            removed.set_len(rm_len);
-        }
-        
-        removed
     }
+    
+    unsafe {
+        tree.data.set_len(new_len);
+        removed.set_len(rm_len);
+    }
+    
+    removed
 }
 
 
 
 
+#[cfg(feature = "nightly")]
 use common::{PlacementId, Align64};
+#[cfg(feature = "nightly")]
 use super::data::ScoredMove;
 
+#[cfg(feature = "nightly")]
 struct AlignedItem {
     it: ScoredMove,
     
@@ -650,6 +665,7 @@ struct AlignedItem {
 }
 
 // TODO: this does not currently provide any performance benefits. See https://github.com/rust-lang/rust/issues/33923
+#[cfg(feature = "nightly")]
 impl WeightedSelectionTreeCopy<PlacementId, ScoredMove> for WeightedSelectionTree<PlacementId, ScoredMove> {
 //    #[inline(never)]
     fn remove_bulk__copy(&mut self, upd_set:&mut Vec<NdIndex>, rm_len:usize, displaced_assoc: Vec<usize>, rm_indices: Vec<usize>) -> Vec<ScoredMove> {
@@ -667,8 +683,7 @@ impl WeightedSelectionTreeCopy<PlacementId, ScoredMove> for WeightedSelectionTre
             unsafe {
                 let src_ptr: *mut ScoredMove = &mut (*data_ptr.offset(src_idx as isize)).item;
                 let src: ScoredMove = ptr::read(src_ptr);
-                let src: AlignedItem = mem::transmute(src);
-                ptr::write(rm_ptr, src);
+                ptr::write(rm_ptr, AlignedItem { it: src, _align: [] });
                 
                 if src_idx < new_len {
                     let displaced = &(*data_ptr.offset((new_len+i) as isize)).item;
@@ -682,9 +697,10 @@ impl WeightedSelectionTreeCopy<PlacementId, ScoredMove> for WeightedSelectionTre
         unsafe {
             self.data.set_len(new_len);
             removed.set_len(rm_len);
-            
-            mem::transmute(removed)
         }
+        
+        // the buffer was allocated with AlignedItem's alignment, so it cannot be handed out as a Vec<ScoredMove>
+        removed.into_iter().map(|aligned| aligned.it).collect()
     }
 }
 
@@ -697,8 +713,9 @@ mod tests {
     use super::WeightedSelectionTree;
     use super::Item;
     use super::Key;
-    use test::Bencher;
-    use test::black_box;
+    use std::ptr;
+    use std::mem;
+    use std::time::Instant;
     
     
     impl Key for i32 {
@@ -869,9 +886,31 @@ mod tests {
         sm.remove_bulk(&remove);
     }
     
-    #[bench]
-    fn remove_bulk_bench(bencher: &mut Bencher) {
-        bencher.iter(|| 
+    // stands in for test::black_box, which is nightly-only
+    fn black_box<T>(dummy: T) -> T {
+        unsafe {
+            let ret = ptr::read_volatile(&dummy);
+            mem::forget(dummy);
+            ret
+        }
+    }
+    
+    // a stable replacement for #[bench]: run with `cargo test --release -- --ignored --nocapture`
+    fn bench<F: FnMut()>(name: &str, iters: u32, mut f: F) {
+        f();
+        let start = Instant::now();
+        for _ in 0..iters {
+            f();
+        }
+        let elapsed = start.elapsed();
+        let nanos = elapsed.as_secs() as f64 * 1e9 + elapsed.subsec_nanos() as f64;
+        println!("{}: {:.0} ns/iter", name, nanos / iters as f64);
+    }
+    
+    #[test]
+    #[ignore]
+    fn remove_bulk_bench() {
+        bench("remove_bulk_bench", 100, || 
             for i in 0..10000 {
                 black_box(i);
                 remove_bulk_benchcase(16, vec![15, 14, 13, 12, 11, 10, 9, 8, 7])
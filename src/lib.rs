@@ -1,10 +1,12 @@
-#![feature(test)]
-#![feature(repr_simd)]
-#![feature(specialization)]
+#![cfg_attr(feature = "nightly", feature(test))]
+#![cfg_attr(feature = "nightly", feature(repr_simd))]
+#![cfg_attr(feature = "nightly", feature(specialization))]
+// the nightly optimizations rely on these unstable features
+#![cfg_attr(feature = "nightly", allow(internal_features, incomplete_features))]
 
 extern crate ndarray;
 extern crate rand;
-extern crate test;
+#[cfg(feature = "nightly")] extern crate test;
 extern crate fnv;
 extern crate bit_set;
 
@@ -12,15 +14,33 @@ extern crate bit_set;
 pub mod fastmath;
 
 #[macro_use] mod common;
+mod dictionary;
 mod fixed_grid;
+mod full_fill;
+mod generator;
 mod global2;
+mod numbering;
+mod render;
+mod rules;
+mod solution;
+mod solution_file;
+mod validate;
 
-pub use self::global2::constructor2::Constructor;
-pub use self::fixed_grid::FixedGrid;
-pub use self::common::{dim, Word, WordId, Orientation, Placement, PlacementId, MatrixDim, LineDim, Problem, Align64};
+pub use self::global2::constructor2::{Constructor, RestartStats};
+pub use self::fixed_grid::{FixedGrid, Eff, objective};
+pub use self::dictionary::{parse_word_list, normalize, enumeration, sample, DicEntry, ScoredWord, WordListFilter};
+pub use self::full_fill::{full_fill, fill_with_blocks, complete_layout, BlockParams, FillFailure};
+pub use self::generator::{random_template, is_connected, slots, fill_with_words, LetterDist, Slot, TemplateParams};
+pub use self::numbering::{Numbering, Entry};
+pub use self::render::{render_svg, render_text, letter_grid, SvgOptions, SvgView};
+pub use self::rules::{LayoutRules, FreeForm, Relaxed, StrictAmerican, WordRuns, Preset};
+pub use self::solution::Solution;
+pub use self::solution_file::{format_solution, parse_solution};
+pub use self::validate::{validate, Violation};
+pub use self::common::{dim, Word, WordId, Orientation, Placement, PlacementId, MatrixDim, LineDim, Problem, Symmetry, Align64};
 // TODO: move sliced_arena to the top level
 pub use self::global2::sliced_arena;
 
 pub mod util {
-	pub use common::{make_rng, tl_rng, xor_rng, AbstractRng};
+	pub use common::{make_rng, seeded_rng, tl_rng, xor_rng, AbstractRng};
 }
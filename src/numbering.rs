@@ -0,0 +1,78 @@
+use std::iter::Chain;
+use std::slice::Iter;
+use common::{dim, Word, Placement, MatrixDim, Orientation};
+
+
+//---- Entry ---------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct Entry {
+	pub number: usize,
+	pub word: Word,
+	pub y: dim,
+	pub x: dim,
+	pub len: dim,
+}
+
+
+//---- Numbering -----------------------------------------------------------------------
+#[derive(Clone, Debug, Default)]
+pub struct Numbering {
+	pub across: Vec<Entry>,
+	pub down: Vec<Entry>,
+}
+
+impl Numbering {
+	/// Assigns standard crossword numbers to the placements: start cells are numbered in row-major order, and an across and
+	/// a down word starting in the same cell share the number. Placements that start off the board get no entry.
+	pub fn new(dim: MatrixDim, placements: &[Placement]) -> Numbering {
+		let on_board = |place: &&Placement| place.y < dim.0 && place.x < dim.1;
+		let mut starts: Vec<(dim, dim)> = placements.iter().filter(on_board).map(|place| (place.y, place.x)).collect();
+		starts.sort();
+		starts.dedup();
+
+		let mut numbering = Numbering::default();
+		for place in placements.iter().filter(on_board) {
+			let number = starts.binary_search(&(place.y, place.x)).unwrap() + 1;
+			let entry = Entry { number, word: place.word.clone(), y: place.y, x: place.x, len: place.word.len() };
+			match place.orientation {
+				Orientation::HOR => numbering.across.push(entry),
+				Orientation::VER => numbering.down.push(entry),
+			}
+		}
+
+		numbering.across.sort_by_key(|a| a.number);
+		numbering.down.sort_by_key(|a| a.number);
+		numbering
+	}
+
+	/// All numbered entries, across first.
+	pub fn entries<'a>(&'a self) -> Chain<Iter<'a, Entry>, Iter<'a, Entry>> {
+		self.across.iter().chain(self.down.iter())
+	}
+}
+
+
+
+#[cfg(test)]
+mod numbering_tests {
+	use super::*;
+	use common::{test_word, Placement, MatrixDim};
+	use common::Orientation::*;
+
+	#[test]
+	fn shared_start_cells_share_a_number() {
+		let places = vec![
+			Placement::new(0, VER, 0, 2, test_word(0, "cat")),
+			Placement::new(1, HOR, 0, 0, test_word(1, "abc")),
+			Placement::new(2, VER, 0, 0, test_word(2, "ad")),
+			Placement::new(3, HOR, 2, 1, test_word(3, "xt")),
+		];
+		let numbering = Numbering::new(MatrixDim(3, 3), &places);
+
+		let across: Vec<_> = numbering.across.iter().map(|e| (e.number, e.word.id, e.y, e.x, e.len)).collect();
+		let down: Vec<_> = numbering.down.iter().map(|e| (e.number, e.word.id, e.y, e.x, e.len)).collect();
+		assert_eq!(across, vec![(1, 1, 0, 0, 3), (3, 3, 2, 1, 2)]);
+		assert_eq!(down, vec![(1, 2, 0, 0, 2), (2, 0, 0, 2, 3)]);
+		assert_eq!(numbering.entries().count(), 4);
+	}
+}
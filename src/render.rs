@@ -0,0 +1,224 @@
+use std::fmt::Write;
+use ndarray::Array;
+use common::{Placement, MatrixDim};
+use numbering::Numbering;
+
+
+//---- SvgView -------------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SvgView {
+	/// Blank grid for solving: blocks and clue numbers, no letters.
+	Puzzle,
+	/// Answer key: the same grid with the letters filled in.
+	Answers,
+}
+
+
+//---- SvgOptions ----------------------------------------------------------------------
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+	pub view: SvgView,
+	/// Side of a single cell, in pixels.
+	pub cell_size: usize,
+	/// Shade the cells where two placements cross.
+	pub highlight_intersections: bool,
+}
+
+impl Default for SvgOptions {
+	fn default() -> SvgOptions {
+		SvgOptions { view: SvgView::Puzzle, cell_size: 32, highlight_intersections: false }
+	}
+}
+
+
+//---- Cell ----------------------------------------------------------------------------
+#[derive(Clone, Copy, Default)]
+struct Cell {
+	letter: Option<char>,
+	words: usize,
+	number: Option<usize>,
+}
+
+
+/// Renders the placements on an `h x w` board as a standalone SVG document. Cells that are not covered by any placement are
+/// drawn as blocks; letters off the board are left out.
+pub fn render_svg(dim: MatrixDim, placements: &[Placement], opts: &SvgOptions) -> String {
+	let cells = build_cells(dim, placements);
+	let MatrixDim(h, w) = dim;
+	let sz = opts.cell_size;
+	let (width, height) = (w*sz + 2, h*sz + 2);
+
+	let mut svg = String::new();
+	writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+			 width, height, width, height).unwrap();
+	writeln!(svg, r#"<g transform="translate(1,1)" font-family="sans-serif">"#).unwrap();
+
+	for y in 0..h {
+		for x in 0..w {
+			let cell = &cells[MatrixDim(y, x)];
+			let (px, py) = (x*sz, y*sz);
+
+			let fill = if cell.words == 0 {
+				"black"
+			} else if cell.words > 1 && opts.highlight_intersections {
+				"#d8d8d8"
+			} else {
+				"white"
+			};
+			writeln!(svg, r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="black" stroke-width="1"/>"#,
+					 px, py, sz, sz, fill).unwrap();
+
+			if let Some(number) = cell.number {
+				writeln!(svg, r#"<text x="{}" y="{}" font-size="{}">{}</text>"#,
+						 px + 2, py + sz*3/10, sz*3/10, number).unwrap();
+			}
+
+			if let (SvgView::Answers, Some(letter)) = (opts.view, cell.letter) {
+				writeln!(svg, r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle">{}</text>"#,
+						 px + sz/2, py + sz*4/5, sz*3/5, xml_escape(&letter.to_uppercase().collect::<String>())).unwrap();
+			}
+		}
+	}
+
+	svg.push_str("</g>\n</svg>\n");
+	svg
+}
+
+
+/// The letter in every cell covered by a placement; letters off the board are left out.
+pub fn letter_grid(dim: MatrixDim, placements: &[Placement]) -> Array<Option<char>, MatrixDim> {
+	let mut letters: Array<Option<char>, MatrixDim> = Array::default(dim);
+	for place in placements {
+		place.fold_positions_index((), |(), y, x, char_idx| {
+			if let Some(letter) = letters.get_mut(MatrixDim(y, x)) {
+				*letter = Some(place.word[char_idx]);
+			}
+		});
+	}
+	letters
+}
+
+
+/// Renders all placements as a single grid of letters, one line per row; cells blocked by the board mask are shown as `#`
+/// and open cells left unfilled as `.`.
+pub fn render_text(board: &Array<bool, MatrixDim>, placements: &[Placement]) -> String {
+	let letters = letter_grid(board.dim(), placements);
+	let MatrixDim(h, w) = board.dim();
+
+	let mut out = String::with_capacity(h * (w+1));
+	for y in 0..h {
+		for x in 0..w {
+			let idx = MatrixDim(y, x);
+			out.push(match (board[idx], letters[idx]) {
+				(_, Some(letter)) => letter,
+				(true, None) => '.',
+				(false, None) => '#',
+			});
+		}
+		out.push('\n');
+	}
+	out
+}
+
+
+fn xml_escape(text: &str) -> String {
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+
+fn build_cells(dim: MatrixDim, placements: &[Placement]) -> Array<Cell, MatrixDim> {
+	let mut cells: Array<Cell, MatrixDim> = Array::default(dim);
+	for place in placements {
+		place.fold_positions_index((), |(), y, x, char_idx| {
+			if let Some(cell) = cells.get_mut(MatrixDim(y, x)) {
+				cell.letter = Some(place.word[char_idx]);
+				cell.words += 1;
+			}
+		});
+	}
+
+	for entry in Numbering::new(dim, placements).entries() {
+		if let Some(cell) = cells.get_mut(MatrixDim(entry.y, entry.x)) {
+			cell.number = Some(entry.number);
+		}
+	}
+
+	cells
+}
+
+
+
+#[cfg(test)]
+mod render_tests {
+	use super::*;
+	use ndarray::Array;
+	use common::{test_word, Placement, MatrixDim};
+	use common::Orientation::*;
+
+	fn placements() -> Vec<Placement> {
+		vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "abc")),
+			Placement::new(1, VER, 0, 0, test_word(1, "ab")),
+		]
+	}
+
+	#[test]
+	fn text_grid_marks_blocks_and_unfilled_cells() {
+		let mut board = Array::from_elem(MatrixDim(2, 3), true);
+		board[MatrixDim(1, 2)] = false;
+
+		assert_eq!(render_text(&board, &placements()), "abc\nb.#\n");
+	}
+
+	#[test]
+	fn non_latin_letters() {
+		let places = vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "шар")),
+			Placement::new(1, VER, 0, 2, test_word(1, "рот")),
+		];
+		let board = Array::from_elem(MatrixDim(3, 3), true);
+		assert_eq!(render_text(&board, &places), "шар\n..о\n..т\n");
+
+		let opts = SvgOptions { view: SvgView::Answers, cell_size: 20, highlight_intersections: false };
+		let svg = render_svg(MatrixDim(3, 3), &places, &opts);
+		assert!(svg.contains(">Ш</text>"));
+		assert_eq!(svg.matches(">Р</text>").count(), 1);
+	}
+
+	#[test]
+	fn puzzle_view_has_numbers_but_no_letters() {
+		let svg = render_svg(MatrixDim(2, 3), &placements(), &SvgOptions::default());
+
+		assert!(svg.starts_with("<svg"));
+		assert_eq!(svg.matches("<rect").count(), 6);
+		assert_eq!(svg.matches("fill=\"black\"").count(), 2);
+		assert!(svg.contains(">1</text>"));
+		assert!(!svg.contains(">2</text>"));
+		assert!(!svg.contains(">A</text>"));
+	}
+
+	#[test]
+	fn answer_view_has_letters_and_highlights() {
+		let opts = SvgOptions { view: SvgView::Answers, cell_size: 20, highlight_intersections: true };
+		let svg = render_svg(MatrixDim(2, 3), &placements(), &opts);
+
+		assert!(svg.contains(">A</text>"));
+		assert!(svg.contains(">C</text>"));
+		assert_eq!(svg.matches("#d8d8d8").count(), 1);
+		assert!(svg.contains("width=\"62\""));
+	}
+
+	#[test]
+	fn letters_off_the_board_are_left_out() {
+		let mut places = placements();
+		places.push(Placement::new(2, VER, 1, 2, test_word(2, "cd")));
+		places.push(Placement::new(3, VER, 5, 5, test_word(3, "ef")));
+		let board = Array::from_elem(MatrixDim(2, 3), true);
+		assert_eq!(render_text(&board, &places), "abc
+b.c
+");
+
+		let svg = render_svg(MatrixDim(2, 3), &places, &SvgOptions { view: SvgView::Answers, ..SvgOptions::default() });
+		assert!(!svg.contains(">D</text>") && !svg.contains(">E</text>"));
+	}
+}
@@ -0,0 +1,237 @@
+use std::rc::Rc;
+use std::collections::HashSet;
+use ndarray::Array;
+use common::{dim, Word, Placement, MatrixDim};
+
+
+//---- LayoutRules ---------------------------------------------------------------------
+/// Decides which placements may share the grid. The constructor only looks for conflicts between placements that have a
+/// letter on, next to or just past the end of a letter of the other, so `compatible` must accept every other pair.
+pub trait LayoutRules {
+	/// Whether `place` may be used on `board` at all.
+	fn admits(&self, place: &Placement, board: &Array<bool, MatrixDim>) -> bool {
+		let _ = (place, board);
+		true
+	}
+
+	/// Whether two placements may both be on the grid; placements of the same word never are.
+	fn compatible(&self, place: &Placement, other: &Placement) -> bool;
+
+	/// Whether compatible placements may put letters side by side. Every such letter then has to be crossed by a perpendicular
+	/// word, otherwise one of the words is dropped when the layout is fixed up.
+	fn side_contacts(&self) -> bool {
+		false
+	}
+
+	/// Whether `run`, a maximal run of letters that side by side words make across their direction, may stand without being
+	/// crossed by a word.
+	fn accepts_run(&self, run: &[char]) -> bool {
+		let _ = run;
+		false
+	}
+}
+
+
+/// Kriss-kross rules, the constructor's default: words cross only on matching letters, do not touch end to end or end to side,
+/// and parallel words do not run side by side on neighbouring lines. Words may touch diagonally at their corners.
+pub struct FreeForm;
+
+impl LayoutRules for FreeForm {
+	fn compatible(&self, place: &Placement, other: &Placement) -> bool {
+		place.compatible(other)
+	}
+}
+
+
+/// Free-form rules, except that parallel words may also run side by side on neighbouring lines, as long as every letter
+/// they put next to each other is crossed by a perpendicular word. Touching at diagonal corners and parallel words with one
+/// line between them are already free-form layouts; side by side words are what this preset adds.
+pub struct Relaxed;
+
+impl LayoutRules for Relaxed {
+	fn compatible(&self, place: &Placement, other: &Placement) -> bool {
+		if place.word.id == other.word.id {
+			return place.id == other.id
+		}
+
+		if place.orientation == other.orientation {
+			let (v0, u0) = place.align(place.orientation);
+			let (v1, u1) = other.align(place.orientation);
+			v0 != v1 || u0 + place.word.len() < u1 || u1 + other.word.len() < u0
+		} else {
+			place.compatible(other)
+		}
+	}
+
+	fn side_contacts(&self) -> bool {
+		true
+	}
+}
+
+
+/// American-style rules: words have at least 3 letters and fill a whole run of open cells, from a block or the border to the
+/// next one; side by side letters must be crossed as with `Relaxed`.
+pub struct StrictAmerican;
+
+impl LayoutRules for StrictAmerican {
+	fn admits(&self, place: &Placement, board: &Array<bool, MatrixDim>) -> bool {
+		let MatrixDim(h, w) = board.dim();
+		let (_, line_len) = place.orientation.align(h, w);
+		let (v, u) = place.align(place.orientation);
+		let len = place.word.len();
+		let blocked = |u: dim| {
+			let (y, x) = place.orientation.align(v, u);
+			!board[MatrixDim(y, x)]
+		};
+
+		len >= 3 && (u == 0 || blocked(u - 1)) && (u + len == line_len || blocked(u + len))
+	}
+
+	fn compatible(&self, place: &Placement, other: &Placement) -> bool {
+		Relaxed.compatible(place, other)
+	}
+
+	fn side_contacts(&self) -> bool {
+		true
+	}
+}
+
+
+/// "Every run is a word": side by side letters need no crossing word if the run they form across the words is a dictionary
+/// word. Placements are otherwise judged by the base rules, so this only matters for rules with side contacts.
+pub struct WordRuns {
+	base: Rc<dyn LayoutRules>,
+	words: HashSet<Vec<char>>,
+}
+
+impl WordRuns {
+	pub fn new(base: Rc<dyn LayoutRules>, dic: &[Word]) -> WordRuns {
+		WordRuns { base, words: dic.iter().map(|word| word.str().to_vec()).collect() }
+	}
+}
+
+impl LayoutRules for WordRuns {
+	fn admits(&self, place: &Placement, board: &Array<bool, MatrixDim>) -> bool {
+		self.base.admits(place, board)
+	}
+
+	fn compatible(&self, place: &Placement, other: &Placement) -> bool {
+		self.base.compatible(place, other)
+	}
+
+	fn side_contacts(&self) -> bool {
+		self.base.side_contacts()
+	}
+
+	fn accepts_run(&self, run: &[char]) -> bool {
+		self.words.contains(run)
+	}
+}
+
+
+/// The maximal run of letters through (y, x) in the direction of the (yd, xd) step; `letter` gives the letter at a cell, or
+/// None for an empty cell or one off the grid.
+pub fn run_through<F: Fn(dim, dim) -> Option<char>>(y: dim, x: dim, yd: dim, xd: dim, letter: F) -> Vec<char> {
+	let (mut y, mut x) = (y, x);
+	while y >= yd && x >= xd && letter(y - yd, x - xd).is_some() {
+		y -= yd;
+		x -= xd;
+	}
+
+	let mut run = vec![];
+	while let Some(c) = letter(y, x) {
+		run.push(c);
+		y += yd;
+		x += xd;
+	}
+	run
+}
+
+
+//---- Preset --------------------------------------------------------------------------
+/// The built-in rule sets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+	StrictAmerican,
+	FreeForm,
+	Relaxed, // free-form plus crossed side by side words
+}
+
+impl Preset {
+	/// Looks up a preset by its command line name: "strict", "free-form" or "relaxed".
+	pub fn from_name(name: &str) -> Option<Preset> {
+		match name {
+			"strict" => Some(Preset::StrictAmerican),
+			"free-form" => Some(Preset::FreeForm),
+			"relaxed" => Some(Preset::Relaxed),
+			_ => None
+		}
+	}
+
+	/// The command line name of the preset, as accepted by `from_name`.
+	pub fn name(self) -> &'static str {
+		match self {
+			Preset::StrictAmerican => "strict",
+			Preset::FreeForm => "free-form",
+			Preset::Relaxed => "relaxed",
+		}
+	}
+
+	pub fn rules(self) -> Rc<dyn LayoutRules> {
+		match self {
+			Preset::StrictAmerican => Rc::new(StrictAmerican),
+			Preset::FreeForm => Rc::new(FreeForm),
+			Preset::Relaxed => Rc::new(Relaxed),
+		}
+	}
+}
+
+
+
+#[cfg(test)]
+mod rules_tests {
+	use super::*;
+	use common::{test_word, Orientation};
+	use common::Orientation::*;
+
+	fn place(id: usize, or: Orientation, y: dim, x: dim, word: &str) -> Placement {
+		Placement::new(id, or, y, x, test_word(id, word))
+	}
+
+	#[test]
+	fn side_by_side_words() {
+		let p1 = place(0, HOR, 0, 0, "abc");
+		let p2 = place(1, HOR, 1, 1, "de");
+		assert!(!FreeForm.compatible(&p1, &p2));
+		assert!(Relaxed.compatible(&p1, &p2) && Relaxed.compatible(&p2, &p1));
+
+		// touching ends stay forbidden
+		let p3 = place(2, HOR, 0, 3, "fg");
+		assert!(!Relaxed.compatible(&p1, &p3));
+		assert!(!StrictAmerican.compatible(&p3, &p1));
+	}
+
+	#[test]
+	fn relaxed_goes_beyond_free_form() {
+		let p1 = place(0, HOR, 1, 1, "abc");
+		let corner = place(1, VER, 2, 4, "de");     // touches the last letter of p1 diagonally
+		let one_line_gap = place(2, HOR, 3, 0, "fgh");
+		let side_by_side = place(3, HOR, 2, 1, "ij");
+		for other in &[corner, one_line_gap] {
+			assert!(FreeForm.compatible(&p1, other) && Relaxed.compatible(&p1, other));
+		}
+		assert!(!FreeForm.compatible(&p1, &side_by_side) && Relaxed.compatible(&p1, &side_by_side));
+	}
+
+	#[test]
+	fn strict_words_fill_runs() {
+		let mut board = Array::from_elem(MatrixDim(2, 5), true);
+		board[MatrixDim(0, 4)] = false;
+
+		assert!(StrictAmerican.admits(&place(0, HOR, 0, 0, "abcd"), &board));
+		assert!(!StrictAmerican.admits(&place(1, HOR, 0, 1, "abc"), &board));   // an open cell before the word
+		assert!(!StrictAmerican.admits(&place(2, VER, 0, 0, "ab"), &board));    // too short
+		assert!(StrictAmerican.admits(&place(3, HOR, 1, 0, "abcde"), &board));
+		assert!(FreeForm.admits(&place(4, HOR, 1, 1, "ab"), &board));
+	}
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+use ndarray::Array;
+use common::{Word, Placement, MatrixDim};
+use fixed_grid::{Eff, objective};
+use numbering::Numbering;
+use render::{self, SvgOptions};
+use solution_file::format_solution;
+
+
+//---- Solution ------------------------------------------------------------------------
+/// A finished layout together with what is known about it: its score and fill, the words that did not make it and how the
+/// search that found it went.
+#[derive(Clone, Debug)]
+pub struct Solution {
+	pub dim: MatrixDim,
+	pub placements: Vec<Placement>,
+	/// The constructor's objective: the number of placed words that cross at least one other word.
+	pub objective: Eff,
+	pub dictionary_size: usize,
+	/// Cells holding a letter.
+	pub letters: usize,
+	/// Cells where two words cross.
+	pub intersections: usize,
+	/// The dictionary words that were not placed, in dictionary order.
+	pub unplaced: Vec<Word>,
+	pub elapsed: Duration,
+	pub rollouts: usize,
+	/// The seed of the search (the restart, with several) that found the layout.
+	pub seed: u32,
+}
+
+impl Solution {
+	pub fn new(dim: MatrixDim, dic: &[Word], placements: Vec<Placement>, elapsed: Duration, rollouts: usize, seed: u32) -> Solution {
+		let mut covers: Array<u8, MatrixDim> = Array::default(dim);
+		for place in placements.iter() {
+			place.fold_positions((), |_, y, x| covers[MatrixDim(y, x)] += 1);
+		}
+
+		let unplaced = dic.iter().filter(|word| placements.iter().all(|place| place.word.id != word.id)).cloned().collect();
+		Solution {
+			dim,
+			objective: objective(dim, &placements),
+			dictionary_size: dic.len(),
+			letters: covers.iter().filter(|&&count| count > 0).count(),
+			intersections: covers.iter().filter(|&&count| count > 1).count(),
+			unplaced,
+			placements,
+			elapsed,
+			rollouts,
+			seed,
+		}
+	}
+
+	pub fn words_placed(&self) -> usize {
+		self.placements.len()
+	}
+
+	/// One line with the score, the fill and the search effort.
+	pub fn summary(&self) -> String {
+		let secs = self.elapsed.as_secs() as f64 + self.elapsed.subsec_nanos() as f64 * 1e-9;
+		format!("score {}, {} of {} words placed, {} letters, {} intersections; {} rollouts in {:.3}s, seed {}",
+				*self.objective, self.words_placed(), self.dictionary_size, self.letters, self.intersections, self.rollouts, secs,
+				self.seed)
+	}
+
+	/// The grid as text, see `render_text`.
+	pub fn render_text(&self, board: &Array<bool, MatrixDim>) -> String {
+		render::render_text(board, &self.placements)
+	}
+
+	/// The grid as an SVG document, see `render_svg`.
+	pub fn render_svg(&self, opts: &SvgOptions) -> String {
+		render::render_svg(self.dim, &self.placements, opts)
+	}
+
+	pub fn numbering(&self) -> Numbering {
+		Numbering::new(self.dim, &self.placements)
+	}
+
+	/// The solution file contents, see `format_solution`.
+	pub fn export(&self) -> String {
+		format_solution(&self.placements)
+	}
+}
+
+
+
+#[cfg(test)]
+mod solution_tests {
+	use super::*;
+	use common::{test_word, Orientation};
+
+	#[test]
+	fn counts_the_fill() {
+		let dic = vec![test_word(0, "cat"), test_word(1, "ant"), test_word(2, "dog")];
+		let placements = vec![Placement::new(0, Orientation::HOR, 0, 0, dic[0].clone()),
+							  Placement::new(1, Orientation::VER, 0, 1, dic[1].clone())];
+		let solution = Solution::new(MatrixDim(3, 3), &dic, placements, Duration::from_millis(1500), 40, 7);
+
+		assert_eq!((*solution.objective, solution.words_placed(), solution.dictionary_size), (2, 2, 3));
+		assert_eq!((solution.letters, solution.intersections), (5, 1));
+		assert_eq!(solution.unplaced.iter().map(|word| word.to_string()).collect::<Vec<_>>(), vec!["dog"]);
+		assert_eq!(solution.summary(), "score 2, 2 of 3 words placed, 5 letters, 1 intersections; 40 rollouts in 1.500s, seed 7");
+		assert_eq!(solution.export(), "0 0 H cat\n0 1 V ant\n");
+	}
+}
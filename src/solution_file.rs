@@ -0,0 +1,93 @@
+use std::str;
+use ndarray::Array;
+use common::{dim, Placement, MatrixDim, Orientation, Problem};
+use dictionary;
+
+// A solution file lists one placement per line: "<row> <col> <H|V> <word>". Empty lines and lines starting with '#' are ignored.
+
+
+pub fn format_solution(placements: &[Placement]) -> String {
+	let mut out = String::new();
+	for place in placements {
+		let or = match place.orientation {
+			Orientation::HOR => 'H',
+			Orientation::VER => 'V',
+		};
+		out.push_str(&format!("{} {} {} {}\n", place.y, place.x, or, place.word));
+	}
+	out
+}
+
+
+/// Parses a solution file. The returned `Problem` holds the words of the solution, one `Word` per distinct string, and the
+/// returned placements share them.
+pub fn parse_solution(bytes: &[u8], board: &Array<bool, MatrixDim>) -> Result<(Problem, Vec<Placement>), String> {
+	let mut words: Vec<String> = vec![];
+	let mut lines: Vec<(Orientation, dim, dim, usize)> = vec![];
+
+	for (line_no, line) in bytes.split(|&b| b == b'\n').enumerate() {
+		let line = str::from_utf8(line).map_err(|_| format!("line {}: not valid UTF-8", line_no+1))?;
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let fields: Vec<&str> = line.split_whitespace().collect();
+		if fields.len() != 4 {
+			return Err(format!("line {}: expected \"<row> <col> <H|V> <word>\", got \"{}\"", line_no+1, line));
+		}
+
+		let y = fields[0].parse::<dim>().map_err(|_| format!("line {}: bad row \"{}\"", line_no+1, fields[0]))?;
+		let x = fields[1].parse::<dim>().map_err(|_| format!("line {}: bad column \"{}\"", line_no+1, fields[1]))?;
+		let or = match fields[2] {
+			"H" | "h" => Orientation::HOR,
+			"V" | "v" => Orientation::VER,
+			other => return Err(format!("line {}: bad orientation \"{}\"", line_no+1, other)),
+		};
+
+		let word: String = fields[3].chars().map(dictionary::lowercase).collect();
+		let word_id = match words.iter().position(|w| *w == word) {
+			Some(id) => id,
+			None => {
+				words.push(word);
+				words.len() - 1
+			}
+		};
+		lines.push((or, y, x, word_id));
+	}
+
+	let problem = Problem::new(words, board.clone());
+	let placements = lines.into_iter().enumerate().map(|(i, (or, y, x, word_id))|
+		Placement::new(i, or, y, x, problem.dic[word_id].clone())
+	).collect();
+
+	Ok((problem, placements))
+}
+
+
+
+#[cfg(test)]
+mod solution_file_tests {
+	use super::*;
+	use ndarray::Array;
+	use common::{test_word, Placement, MatrixDim};
+	use common::Orientation::*;
+
+	#[test]
+	fn round_trip() {
+		let places = vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "abc")),
+			Placement::new(1, VER, 0, 2, test_word(1, "cd")),
+		];
+		let text = format_solution(&places);
+		assert_eq!(text, "0 0 H abc\n0 2 V cd\n");
+
+		let board = Array::from_elem(MatrixDim(3, 3), true);
+		let (problem, parsed) = parse_solution(format!("# comment\n{}\n", text).as_bytes(), &board).unwrap();
+		assert_eq!(problem.dic.len(), 2);
+		assert_eq!(parsed.len(), 2);
+		assert_eq!((parsed[1].orientation, parsed[1].y, parsed[1].x, parsed[1].word.to_string()), (VER, 0, 2, "cd".to_string()));
+
+		assert!(parse_solution(b"0 0 X abc\n", &board).is_err());
+	}
+}
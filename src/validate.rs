@@ -0,0 +1,242 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+use std::cmp::{max, min};
+use ndarray::Array;
+use common::{dim, Word, Placement, MatrixDim};
+use rules::{self, LayoutRules};
+
+
+//---- Violation -----------------------------------------------------------------------
+/// A broken rule; placements are referred to by their index in the validated slice.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+	/// The two placements share a cell, but do not form a valid crossing.
+	Conflict(usize, usize),
+	/// The two placements touch in a way the layout rules forbid, or put letters side by side that no word crosses (and whose
+	/// run across the words the rules do not accept).
+	Adjacent(usize, usize),
+	/// The placement covers a blocked cell or runs off the board at (y, x).
+	OutsideOpenCells(usize, dim, dim),
+	/// The layout rules do not admit the placement, e.g. a word that does not fill its run under the strict rules.
+	NotAdmitted(usize),
+	/// The same word is placed twice.
+	Duplicate(usize, usize),
+	/// The word is not in the dictionary.
+	NotInDictionary(usize),
+}
+
+impl Violation {
+	pub fn describe(&self, placements: &[Placement]) -> String {
+		let p = |i: usize| PlacementDisplay(&placements[i]);
+		match *self {
+			Violation::Conflict(i, j) => format!("{} conflicts with {}", p(i), p(j)),
+			Violation::Adjacent(i, j) => format!("{} is adjacent to {}", p(i), p(j)),
+			Violation::OutsideOpenCells(i, y, x) => format!("{} covers a blocked cell at row {}, col {}", p(i), y, x),
+			Violation::NotAdmitted(i) => format!("{} is not allowed by the layout rules", p(i)),
+			Violation::Duplicate(i, j) => format!("{} duplicates {}", p(j), p(i)),
+			Violation::NotInDictionary(i) => format!("{} is not in the dictionary", p(i)),
+		}
+	}
+}
+
+
+struct PlacementDisplay<'a>(&'a Placement);
+
+impl<'a> fmt::Display for PlacementDisplay<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let place = self.0;
+		write!(f, "'{}' ({:?} at row {}, col {})", place.word, place.orientation, place.y, place.x)
+	}
+}
+
+
+/// Checks hand-made or edited placements against the board mask, the layout rules and the dictionary and reports every
+/// violation found.
+pub fn validate(board: &Array<bool, MatrixDim>, dic: &[Word], placements: &[Placement], rules: &dyn LayoutRules) -> Vec<Violation> {
+	let mut violations = vec![];
+	let MatrixDim(h, w) = board.dim();
+
+	// 1. every letter must be in an open cell
+	for (i, place) in placements.iter().enumerate() {
+		let outside = place.fold_positions(None, |outside, y, x| {
+			if outside.is_none() && (y >= h || x >= w || !board[MatrixDim(y, x)]) {
+				Some((y, x))
+			} else {
+				outside
+			}
+		});
+		if let Some((y, x)) = outside {
+			violations.push(Violation::OutsideOpenCells(i, y, x));
+		} else if !rules.admits(place, board) {
+			violations.push(Violation::NotAdmitted(i));
+		}
+	}
+
+	// 2. pairwise rules; word ids are made unique, so that `compatible` judges the layout alone and duplicates are reported separately
+	let unique: Vec<Placement> = placements.iter().enumerate().map(|(i, place)| {
+		let mut place = place.clone();
+		place.word.id = i;
+		place
+	}).collect();
+
+	for i in 0..unique.len() {
+		for j in i+1..unique.len() {
+			if unique[i].word.str() == unique[j].word.str() {
+				violations.push(Violation::Duplicate(i, j));
+			}
+
+			if !rules.compatible(&unique[i], &unique[j]) {
+				if overlap(&unique[i], &unique[j]) {
+					violations.push(Violation::Conflict(i, j));
+				} else {
+					violations.push(Violation::Adjacent(i, j));
+				}
+			}
+		}
+	}
+
+	// 3. letters the rules let touch side by side must be crossed, or make a run the rules accept
+	if rules.side_contacts() {
+		for (i, j) in side_contacts(board, &unique, rules) {
+			if rules.compatible(&unique[i], &unique[j]) {
+				violations.push(Violation::Adjacent(i, j));
+			}
+		}
+	}
+
+	// 4. every word must come from the dictionary
+	let known: HashSet<&[char]> = dic.iter().map(|word| word.str()).collect();
+	for (i, place) in placements.iter().enumerate() {
+		if !known.contains(place.word.str()) {
+			violations.push(Violation::NotInDictionary(i));
+		}
+	}
+
+	violations
+}
+
+
+/// Pairs of placements (i < j) where a letter of one that no other word crosses has a letter of the other next to it,
+/// across its direction, and the run of letters through them is not accepted by the rules.
+fn side_contacts(board: &Array<bool, MatrixDim>, placements: &[Placement], rules: &dyn LayoutRules) -> BTreeSet<(usize, usize)> {
+	let MatrixDim(h, w) = board.dim();
+	let mut owners: Array<Vec<usize>, MatrixDim> = Array::default(board.dim());
+	for (i, place) in placements.iter().enumerate() {
+		place.fold_positions((), |_, y, x| if y < h && x < w { owners[MatrixDim(y, x)].push(i) });
+	}
+
+	let letter = |y: dim, x: dim| owners.get(MatrixDim(y, x)).and_then(|ids| ids.first()).map(|&i| {
+		let place = &placements[i];
+		place.word[y - place.y + x - place.x]
+	});
+
+	let mut contacts = BTreeSet::new();
+	for (i, place) in placements.iter().enumerate() {
+		let (yd, xd) = place.orientation.align(1, 0);
+		place.fold_positions((), |_, y, x| {
+			if y >= h || x >= w || owners[MatrixDim(y, x)].len() != 1 ||
+			   rules.accepts_run(&rules::run_through(y, x, yd, xd, letter)) {
+				return;
+			}
+			let mut neighbours = vec![(y + yd, x + xd)];
+			if y >= yd && x >= xd {
+				neighbours.push((y - yd, x - xd));
+			}
+			for (ny, nx) in neighbours {
+				if ny < h && nx < w {
+					for &j in owners[MatrixDim(ny, nx)].iter() {
+						contacts.insert((min(i, j), max(i, j)));
+					}
+				}
+			}
+		});
+	}
+	contacts
+}
+
+
+fn overlap(place: &Placement, other: &Placement) -> bool {
+	place.fold_positions(false, |acc, y, x| acc || other.contains(y, x))
+}
+
+
+
+#[cfg(test)]
+mod validate_tests {
+	use super::*;
+	use ndarray::Array;
+	use common::{test_word, Word, Placement, MatrixDim};
+	use common::Orientation::*;
+	use rules::{FreeForm, Relaxed, StrictAmerican, WordRuns};
+	use std::rc::Rc;
+
+	fn board() -> Array<bool, MatrixDim> {
+		let mut board = Array::from_elem(MatrixDim(3, 3), true);
+		board[MatrixDim(2, 2)] = false;
+		board
+	}
+
+	fn dic() -> Vec<Word> {
+		vec![test_word(0, "abc"), test_word(1, "ad"), test_word(2, "cd")]
+	}
+
+	#[test]
+	fn valid_layout() {
+		let places = vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "abc")),
+			Placement::new(1, VER, 0, 0, test_word(1, "ad")),
+		];
+		assert_eq!(validate(&board(), &dic(), &places, &FreeForm), vec![]);
+	}
+
+	#[test]
+	fn reports_every_violation() {
+		let places = vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "abc")),
+			Placement::new(1, VER, 0, 1, test_word(2, "cd")),   // crosses 'b' with 'c'
+			Placement::new(2, HOR, 2, 0, test_word(3, "xyz")),  // runs into the block, not in the dictionary
+			Placement::new(3, VER, 1, 2, test_word(1, "cd")),   // duplicate, touches the end of "abc"
+		];
+		let violations = validate(&board(), &dic(), &places, &FreeForm);
+
+		assert!(violations.contains(&Violation::Conflict(0, 1)));
+		assert!(violations.contains(&Violation::OutsideOpenCells(2, 2, 2)));
+		assert!(violations.contains(&Violation::NotInDictionary(2)));
+		assert!(violations.contains(&Violation::Duplicate(1, 3)));
+		assert!(violations.contains(&Violation::Adjacent(0, 3)));
+		assert!(violations[0].describe(&places).contains("'xyz'"));
+	}
+
+	#[test]
+	fn side_by_side_letters_need_crossing() {
+		let board = Array::from_elem(MatrixDim(3, 3), true);
+		let dic = vec![test_word(0, "abc"), test_word(1, "de"), test_word(2, "bdf")];
+		let places = vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "abc")),
+			Placement::new(1, HOR, 1, 1, test_word(1, "de")),
+		];
+		assert_eq!(validate(&board, &dic, &places, &Relaxed), vec![Violation::Adjacent(0, 1)]);
+
+		// crossing "b" and "d" leaves "c" and "e" side by side
+		let mut crossed = places.clone();
+		crossed.push(Placement::new(2, VER, 0, 1, test_word(2, "bdf")));
+		assert_eq!(validate(&board, &dic, &crossed, &Relaxed).iter().filter(|v| **v == Violation::Adjacent(0, 1)).count(), 1);
+
+		assert!(validate(&board, &dic, &places, &StrictAmerican).contains(&Violation::NotAdmitted(1)));
+	}
+
+	#[test]
+	fn runs_that_are_words() {
+		let board = Array::from_elem(MatrixDim(2, 2), true);
+		let places = vec![
+			Placement::new(0, HOR, 0, 0, test_word(0, "ab")),
+			Placement::new(1, HOR, 1, 0, test_word(1, "cd")),
+		];
+		let dic = vec![test_word(0, "ab"), test_word(1, "cd"), test_word(2, "ac"), test_word(3, "bd")];
+		assert_eq!(validate(&board, &dic, &places, &WordRuns::new(Rc::new(Relaxed), &dic)), vec![]);
+
+		// "bd" is not a word
+		let rules = WordRuns::new(Rc::new(Relaxed), &dic[..3]);
+		assert_eq!(validate(&board, &dic, &places, &rules), vec![Violation::Adjacent(0, 1)]);
+	}
+}
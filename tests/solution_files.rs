@@ -0,0 +1,57 @@
+// Runs the xword binary on solution files that do not fit the board.
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+
+const PROBLEM: &str = "2x2\n__\n__\n-----\nab\nac\nbc\n";
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+fn write_temp(name: &str, contents: &str) -> PathBuf {
+	let path = env::temp_dir().join(format!("xword-{}-{}", std::process::id(), name));
+	File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+	path
+}
+
+fn xword(args: &[&str], solution: &str) -> Output {
+	let run = RUNS.fetch_add(1, Ordering::SeqCst);
+	let problem = write_temp(&format!("{}.xword", run), PROBLEM);
+	let solution = write_temp(&format!("{}.sol", run), solution);
+	let out = Command::new(env!("CARGO_BIN_EXE_xword")).args(args).arg(&problem).arg(&solution).output().unwrap();
+	let _ = std::fs::remove_file(&problem);
+	let _ = std::fs::remove_file(&solution);
+	out
+}
+
+fn assert_rejected(out: &Output) {
+	let stderr = String::from_utf8_lossy(&out.stderr);
+	assert_eq!(out.status.code(), Some(1), "stderr: {}", stderr);
+	assert!(stderr.contains("runs off the 2x2 board"), "stderr: {}", stderr);
+}
+
+
+#[test]
+fn validate_rejects_off_board_placements() {
+	assert_rejected(&xword(&["validate"], "0 1 H ab\n"));
+}
+
+#[test]
+fn render_rejects_off_board_placements() {
+	assert_rejected(&xword(&["render"], "5 5 V ac\n"));
+	assert_rejected(&xword(&["export"], "1 0 V ab\n"));
+}
+
+#[test]
+fn stats_rejects_off_board_placements() {
+	assert_rejected(&xword(&["stats"], "0 0 H ab\n0 2 V bc\n"));
+}
+
+#[test]
+fn placements_on_the_board_are_accepted() {
+	let out = xword(&["render"], "0 0 H ab\n0 0 V ac\n0 1 V bc\n");
+	assert_eq!(out.status.code(), Some(0), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+}